@@ -0,0 +1,416 @@
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike, Utc};
+use tracing::debug;
+
+use crate::Channel;
+
+/// 回看请求涉及的时间点（Unix 时间戳，秒）
+#[derive(Debug, Clone, Copy)]
+pub struct CatchupTimes {
+    pub start: i64,
+    pub end: i64,
+    pub now: i64,
+}
+
+impl CatchupTimes {
+    fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+
+    fn local(timestamp: i64) -> DateTime<Local> {
+        Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .unwrap_or_else(Local::now)
+    }
+
+    fn utc(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now)
+    }
+}
+
+/// 根据频道的 catchup 设置生成回看地址
+///
+/// 支持 append、default、shift、flussonic、xc 几种模式，模板中的占位符会按开始/结束时间展开。
+pub fn build_catchup_url(channel: &Channel, times: CatchupTimes) -> Result<String, String> {
+    if times.end <= times.start {
+        return Err("回看结束时间必须晚于开始时间".to_string());
+    }
+    if times.start > times.now {
+        return Err("回看开始时间不能晚于当前时间".to_string());
+    }
+    if let Some(days) = channel.catchup_days {
+        if times.start < times.now - i64::from(days) * 86400 {
+            return Err(format!("超出回看范围（最多 {} 天）", days));
+        }
+    }
+
+    let source = channel.catchup_source.as_deref().filter(|s| !s.is_empty());
+    let mode = match channel.catchup.as_deref().map(str::to_ascii_lowercase) {
+        Some(mode) => mode,
+        // 只有模板没有模式时，按模板是否为完整 URL 推断
+        None => match source {
+            Some(s) if s.starts_with("http://") || s.starts_with("https://") => "default".to_string(),
+            Some(_) => "append".to_string(),
+            None => return Err(format!("频道 {} 不支持回看", channel.name)),
+        },
+    };
+
+    debug!("生成回看地址: 频道={}, 模式={}", channel.name, mode);
+
+    let template = match mode.as_str() {
+        "append" => {
+            let source = source.ok_or("append 模式缺少 catchup-source")?;
+            append_to_url(&channel.url, source)
+        }
+        "default" => source.ok_or("default 模式缺少 catchup-source")?.to_string(),
+        "shift" => append_to_url(&channel.url, "?utc={utc}&lutc={lutc}"),
+        "flussonic" | "flussonic-hls" | "flussonic-ts" | "fs" => flussonic_template(&channel.url)?,
+        "xc" => xtream_template(&channel.url)?,
+        other => return Err(format!("不支持的回看模式: {}", other)),
+    };
+
+    Ok(expand_template(&template, times))
+}
+
+/// 追加模板到频道 URL，以 `?` 或 `&` 开头的模板会按已有查询参数选择正确的连接符
+fn append_to_url(url: &str, addition: &str) -> String {
+    if !addition.starts_with('?') && !addition.starts_with('&') {
+        return format!("{}{}", url, addition);
+    }
+
+    let addition = addition.trim_start_matches(['?', '&']);
+    if url.ends_with('?') || url.ends_with('&') {
+        format!("{}{}", url, addition)
+    } else if url.contains('?') {
+        format!("{}&{}", url, addition)
+    } else {
+        format!("{}?{}", url, addition)
+    }
+}
+
+/// 把 URL 拆成 `scheme://host`、路径段和查询串（含 `?`）
+fn split_url(url: &str) -> Option<(&str, Vec<&str>, &str)> {
+    let scheme_end = url.find("://")? + 3;
+    let (without_query, query) = match url.find('?') {
+        Some(pos) => (&url[..pos], &url[pos..]),
+        None => (url, ""),
+    };
+    let host_end = without_query[scheme_end..]
+        .find('/')
+        .map(|pos| scheme_end + pos)
+        .unwrap_or(without_query.len());
+    let segments = without_query[host_end..]
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    Some((&without_query[..host_end], segments, query))
+}
+
+/// Flussonic: `.../stream/index.m3u8` -> `.../stream/index-{utc}-{duration}.m3u8`，
+/// `.../stream/mpegts` -> `.../stream/timeshift_abs-{utc}.ts`
+fn flussonic_template(url: &str) -> Result<String, String> {
+    let invalid = || format!("无法识别的 Flussonic 地址: {}", url);
+    let (host, mut segments, query) = split_url(url).ok_or_else(invalid)?;
+    let file = segments.pop().ok_or_else(invalid)?;
+    if segments.is_empty() {
+        return Err(invalid());
+    }
+    let stream = segments.join("/");
+
+    if file == "mpegts" {
+        Ok(format!("{}/{}/timeshift_abs-{{utc}}.ts{}", host, stream, query))
+    } else if let Some(stem) = file.strip_suffix(".m3u8") {
+        Ok(format!("{}/{}/{}-{{utc}}-{{duration}}.m3u8{}", host, stream, stem, query))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Xtream Codes: `host/[live/]user/pass/id.ext` -> `host/timeshift/user/pass/{分钟}/{Y}-{m}-{d}:{H}-{M}/id.ext`
+fn xtream_template(url: &str) -> Result<String, String> {
+    let invalid = || format!("无法识别的 Xtream Codes 地址: {}", url);
+    let (host, mut segments, query) = split_url(url).ok_or_else(invalid)?;
+    if segments.first() == Some(&"live") {
+        segments.remove(0);
+    }
+    let [user, pass, file] = segments[..] else {
+        return Err(invalid());
+    };
+    let (id, ext) = file.rsplit_once('.').unwrap_or((file, "ts"));
+
+    Ok(format!(
+        "{}/timeshift/{}/{}/{{duration:60}}/{{Y}}-{{m}}-{{d}}:{{H}}-{{M}}/{}.{}{}",
+        host, user, pass, id, ext, query
+    ))
+}
+
+/// 展开模板中的占位符
+///
+/// - `{utc}` / `${start}`、`{utcend}` / `${end}`、`{lutc}` / `${now}` / `${timestamp}`：Unix 时间戳
+/// - `{duration}` / `{duration:N}`、`{offset}` / `{offset:N}`：秒数（可除以 N）
+/// - `{Y}` `{m}` `{d}` `{H}` `{M}` `{S}`：开始时间（本地时间）的各个部分
+/// - `{utc:Y-m-d H:M:S}`、`{utcend:...}`：按字母格式化开始/结束时间（UTC）
+/// - `${(b)yyyyMMddHHmmss}`、`${(e)...}`：按 Java 风格格式化开始/结束时间（本地时间）
+pub fn expand_template(template: &str, times: CatchupTimes) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|pos| open + pos) else {
+            break;
+        };
+
+        // `${...}` 与 `{...}` 等价
+        let prefix_end = if open > 0 && rest.as_bytes()[open - 1] == b'$' { open - 1 } else { open };
+        let token = &rest[open + 1..close];
+
+        match expand_placeholder(token, times) {
+            Some(value) => {
+                output.push_str(&rest[..prefix_end]);
+                output.push_str(&value);
+            }
+            None => output.push_str(&rest[..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn expand_placeholder(token: &str, times: CatchupTimes) -> Option<String> {
+    let start = CatchupTimes::local(times.start);
+    let end = CatchupTimes::local(times.end);
+
+    let value = match token {
+        "utc" | "start" => times.start.to_string(),
+        "utcend" | "end" => times.end.to_string(),
+        "lutc" | "now" | "timestamp" => times.now.to_string(),
+        "duration" => times.duration().to_string(),
+        "offset" => (times.now - times.start).to_string(),
+        "Y" => format!("{:04}", start.year()),
+        "m" => format!("{:02}", start.month()),
+        "d" => format!("{:02}", start.day()),
+        "H" => format!("{:02}", start.hour()),
+        "M" => format!("{:02}", start.minute()),
+        "S" => format!("{:02}", start.second()),
+        _ => {
+            if let Some(format) = token.strip_prefix("(b)") {
+                format_java(&start, format)
+            } else if let Some(format) = token.strip_prefix("(e)") {
+                format_java(&end, format)
+            } else if let Some((name, arg)) = token.split_once(':') {
+                match name {
+                    "duration" => (times.duration() / arg.parse::<i64>().ok().filter(|d| *d > 0)?).to_string(),
+                    "offset" => ((times.now - times.start) / arg.parse::<i64>().ok().filter(|d| *d > 0)?).to_string(),
+                    // 与 Kodi 一致，带格式的 utc / utcend 按 UTC 时间展开，只有 ${(b)} / ${(e)} 使用本地时间
+                    "utc" | "start" => format_letters(&CatchupTimes::utc(times.start), arg),
+                    "utcend" | "end" => format_letters(&CatchupTimes::utc(times.end), arg),
+                    _ => return None,
+                }
+            } else {
+                return None;
+            }
+        }
+    };
+
+    Some(value)
+}
+
+/// Kodi 风格的字母格式：`Y-m-d H:M:S`
+fn format_letters<Tz: TimeZone>(time: &DateTime<Tz>, format: &str) -> String {
+    format
+        .chars()
+        .map(|c| match c {
+            'Y' => format!("{:04}", time.year()),
+            'm' => format!("{:02}", time.month()),
+            'd' => format!("{:02}", time.day()),
+            'H' => format!("{:02}", time.hour()),
+            'M' => format!("{:02}", time.minute()),
+            'S' => format!("{:02}", time.second()),
+            other => other.to_string(),
+        })
+        .collect()
+}
+
+/// Java `SimpleDateFormat` 风格的格式：`yyyyMMddHHmmss`，单引号内为原样文本
+fn format_java(time: &DateTime<Local>, format: &str) -> String {
+    let chars: Vec<char> = format.chars().collect();
+    let mut output = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\'' {
+            let literal_end = chars[i + 1..]
+                .iter()
+                .position(|&ch| ch == '\'')
+                .map(|pos| i + 1 + pos)
+                .unwrap_or(chars.len());
+            output.extend(&chars[i + 1..literal_end]);
+            i = literal_end + 1;
+            continue;
+        }
+
+        let mut run = 1;
+        while i + run < chars.len() && chars[i + run] == c {
+            run += 1;
+        }
+
+        match c {
+            'y' if run == 2 => output.push_str(&format!("{:02}", time.year() % 100)),
+            'y' => output.push_str(&format!("{:04}", time.year())),
+            'M' | 'd' | 'H' | 'm' | 's' => {
+                let number = match c {
+                    'M' => time.month(),
+                    'd' => time.day(),
+                    'H' => time.hour(),
+                    'm' => time.minute(),
+                    _ => time.second(),
+                };
+                output.push_str(&format!("{:0width$}", number, width = run));
+            }
+            _ => output.extend(std::iter::repeat_n(c, run)),
+        }
+        i += run;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-02 03:04:05（本地时间）开始的一小时，十分钟后请求
+    fn times() -> CatchupTimes {
+        let start = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap().timestamp();
+        CatchupTimes {
+            start,
+            end: start + 3600,
+            now: start + 4200,
+        }
+    }
+
+    fn channel(url: &str, catchup: Option<&str>, source: Option<&str>) -> Channel {
+        Channel {
+            name: "测试频道".to_string(),
+            url: url.to_string(),
+            catchup: catchup.map(str::to_string),
+            catchup_source: source.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn append_mode_picks_query_separator() {
+        let t = times();
+        let url = build_catchup_url(&channel("http://a.com/live.m3u8", Some("append"), Some("?start={utc}")), t).unwrap();
+        assert_eq!(url, format!("http://a.com/live.m3u8?start={}", t.start));
+
+        let url = build_catchup_url(&channel("http://a.com/live.m3u8?token=1", Some("append"), Some("?start={utc}")), t).unwrap();
+        assert_eq!(url, format!("http://a.com/live.m3u8?token=1&start={}", t.start));
+
+        let url = build_catchup_url(&channel("http://a.com/live", Some("append"), Some("/{utc}.m3u8")), t).unwrap();
+        assert_eq!(url, format!("http://a.com/live/{}.m3u8", t.start));
+    }
+
+    #[test]
+    fn default_mode_uses_source_as_is() {
+        let t = times();
+        let url = build_catchup_url(
+            &channel("http://a.com/live.m3u8", Some("default"), Some("http://b.com/tv?b=${(b)yyyyMMddHHmmss}&e=${(e)yyyyMMddHHmmss}")),
+            t,
+        )
+        .unwrap();
+        assert_eq!(url, "http://b.com/tv?b=20240102030405&e=20240102040405");
+    }
+
+    #[test]
+    fn mode_is_inferred_from_source() {
+        let t = times();
+        let url = build_catchup_url(&channel("http://a.com/live", None, Some("http://b.com/{utc}")), t).unwrap();
+        assert_eq!(url, format!("http://b.com/{}", t.start));
+
+        let url = build_catchup_url(&channel("http://a.com/live", None, Some("?s={utc}")), t).unwrap();
+        assert_eq!(url, format!("http://a.com/live?s={}", t.start));
+
+        assert!(build_catchup_url(&channel("http://a.com/live", None, None), t).is_err());
+    }
+
+    #[test]
+    fn shift_mode_appends_utc_and_lutc() {
+        let t = times();
+        let url = build_catchup_url(&channel("http://a.com/live.m3u8?x=1", Some("shift"), None), t).unwrap();
+        assert_eq!(url, format!("http://a.com/live.m3u8?x=1&utc={}&lutc={}", t.start, t.now));
+    }
+
+    #[test]
+    fn flussonic_mode_rewrites_path() {
+        let t = times();
+        let url = build_catchup_url(&channel("http://a.com/ch1/index.m3u8?token=x", Some("flussonic"), None), t).unwrap();
+        assert_eq!(url, format!("http://a.com/ch1/index-{}-3600.m3u8?token=x", t.start));
+
+        let url = build_catchup_url(&channel("http://a.com/ch1/mpegts", Some("fs"), None), t).unwrap();
+        assert_eq!(url, format!("http://a.com/ch1/timeshift_abs-{}.ts", t.start));
+
+        assert!(build_catchup_url(&channel("http://a.com/index.m3u8", Some("flussonic"), None), t).is_err());
+    }
+
+    #[test]
+    fn xc_mode_builds_timeshift_url() {
+        let t = times();
+        let url = build_catchup_url(&channel("http://a.com:8080/live/user/pass/123.ts", Some("xc"), None), t).unwrap();
+        assert_eq!(url, "http://a.com:8080/timeshift/user/pass/60/2024-01-02:03-04/123.ts");
+
+        let url = build_catchup_url(&channel("http://a.com/user/pass/123", Some("xc"), None), t).unwrap();
+        assert_eq!(url, "http://a.com/timeshift/user/pass/60/2024-01-02:03-04/123.ts");
+    }
+
+    #[test]
+    fn rejects_invalid_ranges_and_modes() {
+        let t = times();
+        let mut ch = channel("http://a.com/live", Some("shift"), None);
+        assert!(build_catchup_url(&ch, CatchupTimes { end: t.start, ..t }).is_err());
+        assert!(build_catchup_url(&ch, CatchupTimes { now: t.start - 1, ..t }).is_err());
+
+        ch.catchup_days = Some(1);
+        assert!(build_catchup_url(&ch, CatchupTimes { now: t.start + 2 * 86400, ..t }).is_err());
+        assert!(build_catchup_url(&ch, t).is_ok());
+
+        ch.catchup = Some("unknown".to_string());
+        assert!(build_catchup_url(&ch, t).is_err());
+    }
+
+    #[test]
+    fn expands_every_placeholder() {
+        let t = times();
+        assert_eq!(
+            expand_template("{utc}|${start}|{utcend}|${end}|{lutc}|${now}|${timestamp}", t),
+            format!("{0}|{0}|{1}|{1}|{2}|{2}|{2}", t.start, t.end, t.now)
+        );
+        assert_eq!(expand_template("{duration}|{duration:60}|{offset}|{offset:60}", t), "3600|60|4200|70");
+        assert_eq!(expand_template("{Y}{m}{d}{H}{M}{S}", t), "20240102030405");
+        assert_eq!(expand_template("${(b)yy-MM-dd'T'HH:mm:ss}|${(e)yyyyMMdd}", t), "24-01-02T03:04:05|20240102");
+    }
+
+    #[test]
+    fn formatted_utc_placeholders_use_utc() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap().timestamp();
+        let t = CatchupTimes {
+            start,
+            end: start + 3600,
+            now: start + 4200,
+        };
+        assert_eq!(expand_template("{utc:Y-m-d H:M:S}|{utcend:YmdHMS}", t), "2024-01-02 03:04:05|20240102040405");
+        assert_eq!(expand_template("${start:YmdHMS}|${end:YmdHMS}", t), "20240102030405|20240102040405");
+    }
+
+    #[test]
+    fn keeps_unknown_and_unterminated_placeholders() {
+        let t = times();
+        assert_eq!(expand_template("{unknown}/{duration:0}/${foo}", t), "{unknown}/{duration:0}/${foo}");
+        assert_eq!(expand_template("a{utc", t), "a{utc");
+    }
+}
@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// 用户设置的频道别名保存在数据目录下
+const ALIASES_FILE: &str = "channel_aliases.json";
+
+/// 名称末尾表示画质的后缀（小写），长的在前
+const QUALITY_SUFFIXES: &[&str] = &[
+    "超高清", "高清", "超清", "标清", "蓝光", "2160p", "1080p", "1080i", "720p", "576p", "480p", "60fps", "50fps", "hevc", "h265", "h264", "uhd",
+    "fhd", "hdr", "hd", "sd", "4k", "8k",
+];
+
+/// 编号频道后的频道描述（`CCTV1综合`），长的在前；不在列表中的文字（`CCTV4欧洲`）是名称的一部分
+const DESCRIPTION_SUFFIXES: &[&str] = &[
+    "中文国际", "国防军事", "社会与法", "农业农村", "综合", "财经", "综艺", "体育", "电影", "军事", "电视剧", "纪录", "科教", "戏曲", "新闻",
+    "少儿", "音乐", "农业",
+];
+
+/// 频道名中常见的繁体字 -> 简体字，成对排列
+const TRADITIONAL_PAIRS: &str = "衛卫視视臺台鳳凤亞亚東东華华電电劇剧綜综藝艺聞闻體体財财經经國国際际頻频龍龙廣广灣湾戲戏樂乐紀纪錄录\
+兒儿動动畫画無无線线聯联緯纬來来記记實实時时間间軍军農农業业類类歷历環环運运車车氣气專专區区數数碼码學学習习黃黄資资訊讯門门開开陽阳鄉乡\
+莊庄寧宁遼辽雲云貴贵陝陕蘇苏瀋沈權权熱热點点購购導导歡欢鏡镜選选鬥斗漢汉語语場场紅红藍蓝綠绿萬万達达廈厦陸陆書书風风韻韵響响戰战雙双勁劲\
+愛爱萊莱塢坞縣县島岛灘滩圖图館馆聲声標标號号優优質质錢钱馬马魚鱼";
+
+/// 分隔符，去掉后 `CCTV-1`、`CCTV 1`、`CCTV_1` 是同一个名称
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '-' | '_' | '.' | '·' | '•' | '|' | '/' | '\\' | ':' | '\'' | '"' | '—' | '–')
+}
+
+fn closing_bracket(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '<' => Some('>'),
+        '【' => Some('】'),
+        '《' => Some('》'),
+        '「' => Some('」'),
+        _ => None,
+    }
+}
+
+/// 全角字符转为半角（`ＣＣＴＶ１` -> `CCTV1`，全角括号、加号等同样转换）
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn to_simplified(c: char) -> char {
+    let mut chars = TRADITIONAL_PAIRS.chars();
+    while let (Some(traditional), Some(simplified)) = (chars.next(), chars.next()) {
+        if traditional == c {
+            return simplified;
+        }
+    }
+    c
+}
+
+/// 去掉括号及其中的内容（`湖南卫视(备用)`），整个名称都在括号中时只去掉括号
+fn strip_brackets(name: &str) -> String {
+    let mut output = String::new();
+    let mut closing: Vec<char> = Vec::new();
+    for c in name.chars() {
+        if let Some(close) = closing_bracket(c) {
+            closing.push(close);
+        } else if closing.last() == Some(&c) {
+            closing.pop();
+        } else if closing.is_empty() {
+            output.push(c);
+        }
+    }
+    if output.chars().all(is_separator) {
+        return name.chars().filter(|c| closing_bracket(*c).is_none() && !")]>】》」".contains(*c)).collect();
+    }
+    output
+}
+
+/// 去掉末尾的画质后缀，可以有多个（`CCTV1 HD 1080p`）
+///
+/// 只剩字母时 4K / 8K 是频道名的一部分（`CCTV4K`），不去掉。
+fn strip_quality(name: &mut String) {
+    loop {
+        let lower = name.to_ascii_lowercase();
+        let suffix = QUALITY_SUFFIXES.iter().find(|suffix| {
+            let Some(rest) = lower.strip_suffix(*suffix) else {
+                return false;
+            };
+            let numbered_channel = matches!(**suffix, "4k" | "8k") && rest.bytes().all(|b| b.is_ascii_alphabetic());
+            !rest.is_empty() && !numbered_channel
+        });
+        match suffix {
+            Some(suffix) => name.truncate(name.len() - suffix.len()),
+            None => return,
+        }
+    }
+}
+
+/// 文本是否只由画质后缀组成（`HD`、`HD1080p`），空文本也算
+fn is_quality_only(text: &str) -> bool {
+    let mut rest = text.to_ascii_lowercase();
+    while !rest.is_empty() {
+        match QUALITY_SUFFIXES.iter().find(|suffix| rest.ends_with(*suffix)) {
+            Some(suffix) => rest.truncate(rest.len() - suffix.len()),
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 字母加数字编号的频道去掉编号后的描述（`CCTV1综合`、`CCTV01`），其他文字保留（`CCTV4欧洲`）
+fn strip_description(name: &mut String) {
+    let letters = name.bytes().take_while(u8::is_ascii_alphabetic).count();
+    let digits = name[letters..].bytes().take_while(u8::is_ascii_digit).count();
+    if letters == 0 || digits == 0 {
+        return;
+    }
+    let mut code_end = letters + digits;
+    if name[code_end..].starts_with('+') {
+        code_end += 1;
+    }
+
+    // 编号后只有已知的描述和画质后缀时去掉
+    let rest = &name[code_end..];
+    let rest = DESCRIPTION_SUFFIXES.iter().find_map(|d| rest.strip_prefix(d)).unwrap_or(rest);
+    if !is_quality_only(rest) {
+        return;
+    }
+
+    let number = name[letters..letters + digits].trim_start_matches('0');
+    let number = if number.is_empty() { "0" } else { number };
+    *name = format!("{}{}{}", &name[..letters], number, &name[letters + digits..code_end]);
+}
+
+/// 规范化的频道名，保留大小写，用于台标模板和 DIYP 接口
+///
+/// 全角转半角、繁体转简体，去掉括号中的内容、分隔符、画质后缀，以及编号频道后的描述：
+/// `CCTV-1综合`、`ＣＣＴＶ１`、`CCTV 1 HD` 都规范化为 `CCTV1`。
+pub fn canonical(name: &str) -> String {
+    let converted: String = name.chars().map(to_half_width).map(to_simplified).collect();
+    let mut output: String = strip_brackets(&converted).chars().filter(|c| !is_separator(*c)).collect();
+    strip_quality(&mut output);
+    strip_description(&mut output);
+    if output.is_empty() {
+        return name.trim().to_string();
+    }
+    output
+}
+
+/// 不区分大小写比较频道名时使用的键
+pub fn match_key(name: &str) -> String {
+    canonical(name).to_lowercase()
+}
+
+/// 频道别名：播放列表中的频道名 -> 节目单和台标使用的名称
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelAlias {
+    pub name: String,
+    pub target: String,
+}
+
+/// 用户设置的频道别名，处理规范化后仍然无法匹配的名称（`凤凰中文` -> `凤凰卫视中文台`）
+pub struct ChannelAliases {
+    path: PathBuf,
+    aliases: Mutex<Vec<ChannelAlias>>,
+    /// 别名的匹配键 -> 对应的名称
+    index: Mutex<HashMap<String, String>>,
+}
+
+impl ChannelAliases {
+    /// 读取保存的别名，文件不存在或无法解析时为空
+    pub fn load(data_dir: &Path) -> ChannelAliases {
+        let path = data_dir.join(ALIASES_FILE);
+        let aliases: Vec<ChannelAlias> = if path.exists() {
+            match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            {
+                Ok(aliases) => aliases,
+                Err(e) => {
+                    warn!("读取频道别名失败: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        debug!("已加载 {} 个频道别名", aliases.len());
+        let index = build_index(&aliases);
+        ChannelAliases {
+            path,
+            aliases: Mutex::new(aliases),
+            index: Mutex::new(index),
+        }
+    }
+
+    pub fn list(&self) -> Vec<ChannelAlias> {
+        self.aliases.lock().unwrap().clone()
+    }
+
+    /// 替换全部别名并保存；名称为空的条目被忽略，同一名称出现多次时使用最后一个
+    pub fn set(&self, aliases: Vec<ChannelAlias>) -> Result<Vec<ChannelAlias>, String> {
+        let mut cleaned: Vec<ChannelAlias> = Vec::new();
+        for alias in aliases {
+            let alias = ChannelAlias {
+                name: alias.name.trim().to_string(),
+                target: alias.target.trim().to_string(),
+            };
+            if alias.name.is_empty() || alias.target.is_empty() {
+                continue;
+            }
+            cleaned.retain(|a| match_key(&a.name) != match_key(&alias.name));
+            cleaned.push(alias);
+        }
+
+        let json = serde_json::to_string_pretty(&cleaned).map_err(|e| format!("序列化失败: {}", e))?;
+        fs::write(&self.path, json).map_err(|e| format!("写入文件失败: {}", e))?;
+        info!("已保存 {} 个频道别名", cleaned.len());
+
+        *self.index.lock().unwrap() = build_index(&cleaned);
+        *self.aliases.lock().unwrap() = cleaned.clone();
+        Ok(cleaned)
+    }
+
+    /// 应用别名后的频道名，没有别名时原样返回
+    pub fn resolve<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.index.lock().unwrap().get(&match_key(name)) {
+            Some(target) => Cow::Owned(target.clone()),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    /// 应用别名后规范化的频道名
+    pub fn canonical(&self, name: &str) -> String {
+        canonical(&self.resolve(name))
+    }
+
+    /// 匹配频道名时依次尝试的键：有别名时先用别名，再用名称本身
+    pub fn match_keys(&self, name: &str) -> Vec<String> {
+        let key = match_key(name);
+        match self.index.lock().unwrap().get(&key) {
+            Some(target) => vec![match_key(target), key],
+            None => vec![key],
+        }
+    }
+}
+
+fn build_index(aliases: &[ChannelAlias]) -> HashMap<String, String> {
+    aliases.iter().map(|a| (match_key(&a.name), a.target.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_channels_share_one_name() {
+        for name in ["CCTV-1综合", "CCTV1", "CCTV 1 HD", "ＣＣＴＶ１", "CCTV01", "CCTV-1 综合 高清", "CCTV1(备用)", "cctv_1 1080p"] {
+            assert_eq!(match_key(name), "cctv1", "{}", name);
+        }
+        assert_eq!(canonical("CCTV-1综合"), "CCTV1");
+        assert_eq!(canonical("CCTV 1 HD"), "CCTV1");
+        assert_eq!(canonical("CCTV-5+ 体育"), "CCTV5+");
+        assert_eq!(canonical("CCTV-13 新闻"), "CCTV13");
+    }
+
+    #[test]
+    fn keeps_text_that_is_not_a_description() {
+        assert_eq!(canonical("CCTV4欧洲"), "CCTV4欧洲");
+        assert_eq!(canonical("CCTV4美洲"), "CCTV4美洲");
+        assert_eq!(canonical("CCTV4亚洲"), "CCTV4亚洲");
+        assert_ne!(match_key("CCTV4欧洲"), match_key("CCTV4美洲"));
+        assert_eq!(canonical("CCTV5+体育赛事"), "CCTV5+体育赛事");
+        assert_ne!(match_key("CCTV5+体育赛事"), match_key("CCTV5"));
+    }
+
+    #[test]
+    fn keeps_4k_channel_names() {
+        assert_eq!(canonical("CCTV4K"), "CCTV4K");
+        assert_eq!(canonical("CCTV-4K 超高清"), "CCTV4K");
+        assert_ne!(match_key("CCTV4K"), match_key("CCTV4"));
+        assert_eq!(canonical("CCTV4 4K"), "CCTV4");
+    }
+
+    #[test]
+    fn normalizes_other_names() {
+        assert_eq!(canonical("湖南衛視 HD"), "湖南卫视");
+        assert_eq!(canonical("【凤凰卫视】"), "凤凰卫视");
+        assert_eq!(canonical("  "), "");
+    }
+}
@@ -0,0 +1,224 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use tracing::{debug, info};
+
+/// 用于判断编码的样本大小（从第一个非 ASCII 字节开始）
+const SNIFF_LEN: usize = 16 * 1024;
+
+/// 从 `Content-Type` 中读取 charset，例如 `text/plain; charset=gbk`
+pub fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches('"').as_bytes())
+    })
+}
+
+/// 样本是否为合法 UTF-8（允许末尾被截断的字符）
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// 按声明的编码和内容推断编码
+///
+/// 服务器声明了非 UTF-8 的编码时直接采用；声明为 UTF-8（或未声明）但内容不是合法 UTF-8 时，
+/// 按中文环境做启发式检测（GBK / GB18030 / Big5 等）。
+fn detect(sample: &[u8], declared: Option<&'static Encoding>) -> &'static Encoding {
+    if let Some(declared) = declared.filter(|e| *e != UTF_8) {
+        return declared;
+    }
+    if is_utf8(sample) {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(sample, true);
+    let guess = detector.guess(Some(b"cn"), true);
+    debug!("启发式检测编码: {}", guess.name());
+    guess
+}
+
+/// 把整个文件内容转为 UTF-8，依次按 BOM、声明的 charset 和内容检测编码
+pub fn decode(bytes: &[u8], content_type: Option<&str>) -> (String, &'static Encoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding);
+    }
+
+    let declared = content_type.and_then(charset_from_content_type);
+    let start = bytes.iter().position(|b| !b.is_ascii()).unwrap_or(bytes.len());
+    let sample = &bytes[start..bytes.len().min(start + SNIFF_LEN)];
+    let encoding = if sample.is_empty() {
+        declared.unwrap_or(UTF_8)
+    } else {
+        detect(sample, declared)
+    };
+
+    if encoding != UTF_8 {
+        info!("内容编码为 {}，转换为 UTF-8", encoding.name());
+    }
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding)
+}
+
+/// 按行转码：纯 ASCII 的行在任何兼容编码下都一样，可以直接输出；
+/// 遇到第一个非 ASCII 的行后先缓存，攒够样本再判断编码。
+pub struct LineDecoder {
+    encoding: Option<&'static Encoding>,
+    declared: Option<&'static Encoding>,
+    pending: Vec<Vec<u8>>,
+    pending_len: usize,
+}
+
+impl LineDecoder {
+    pub fn new(content_type: Option<&str>) -> LineDecoder {
+        LineDecoder {
+            encoding: None,
+            declared: content_type.and_then(charset_from_content_type),
+            pending: Vec::new(),
+            pending_len: 0,
+        }
+    }
+
+    /// 已由 BOM 确定编码
+    pub fn set_encoding(&mut self, encoding: &'static Encoding) {
+        self.encoding = Some(encoding);
+    }
+
+    /// 检测到的编码（内容全部为 ASCII 时为声明的编码或 UTF-8）
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding.or(self.declared).unwrap_or(UTF_8)
+    }
+
+    /// 输入一行原始字节，可以输出的行交给 `emit`
+    pub fn push(&mut self, line: &[u8], emit: &mut impl FnMut(&str)) {
+        if let Some(encoding) = self.encoding {
+            emit(&encoding.decode_without_bom_handling(line).0);
+            return;
+        }
+        if self.pending.is_empty() && line.is_ascii() {
+            // ASCII 在 UTF-8 / GBK 中相同
+            emit(std::str::from_utf8(line).unwrap_or_default());
+            return;
+        }
+
+        self.pending_len += line.len() + 1;
+        self.pending.push(line.to_vec());
+        if self.pending_len >= SNIFF_LEN {
+            self.flush(emit);
+        }
+    }
+
+    /// 输入结束，输出缓存的行
+    pub fn finish(&mut self, emit: &mut impl FnMut(&str)) {
+        if !self.pending.is_empty() {
+            self.flush(emit);
+        }
+    }
+
+    fn flush(&mut self, emit: &mut impl FnMut(&str)) {
+        let sample = self.pending.join(&b'\n');
+        let encoding = detect(&sample, self.declared);
+        if encoding != UTF_8 {
+            info!("内容编码为 {}，转换为 UTF-8", encoding.name());
+        }
+        self.encoding = Some(encoding);
+
+        self.pending_len = 0;
+        for line in std::mem::take(&mut self.pending) {
+            emit(&encoding.decode_without_bom_handling(&line).0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{GBK, UTF_16BE, UTF_16LE};
+
+    const PLAYLIST: &str = "#EXTM3U\n#EXTINF:-1 group-title=\"央视频道\",CCTV1 综合频道\nhttp://a.com/1.m3u8\n\
+                            #EXTINF:-1 group-title=\"卫视频道\",湖南卫视 高清\nhttp://a.com/2.m3u8\n";
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = if big_endian { vec![0xFE, 0xFF] } else { vec![0xFF, 0xFE] };
+        for unit in text.encode_utf16() {
+            let pair = if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() };
+            bytes.extend_from_slice(&pair);
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_charset_from_content_type() {
+        assert_eq!(charset_from_content_type("text/plain; charset=gbk"), Some(GBK));
+        assert_eq!(charset_from_content_type("audio/x-mpegurl;Charset=\"UTF-8\""), Some(UTF_8));
+        assert_eq!(charset_from_content_type("text/plain"), None);
+        assert_eq!(charset_from_content_type("text/plain; charset=unknown"), None);
+    }
+
+    #[test]
+    fn detects_undeclared_gbk() {
+        let (bytes, _, _) = GBK.encode(PLAYLIST);
+        let (text, encoding) = decode(&bytes, None);
+        assert_eq!(encoding, GBK);
+        assert_eq!(text, PLAYLIST);
+
+        // 声明为 UTF-8 但内容不是合法 UTF-8 时同样检测
+        let (text, encoding) = decode(&bytes, Some("text/plain; charset=utf-8"));
+        assert_eq!(encoding, GBK);
+        assert_eq!(text, PLAYLIST);
+    }
+
+    #[test]
+    fn uses_declared_non_utf8_charset() {
+        let (bytes, _, _) = GBK.encode("CCTV1,http://a.com/1.m3u8\n中文,http://a.com/2.m3u8\n");
+        let (text, encoding) = decode(&bytes, Some("text/plain; charset=GB2312"));
+        assert_eq!(encoding, GBK);
+        assert_eq!(text, "CCTV1,http://a.com/1.m3u8\n中文,http://a.com/2.m3u8\n");
+    }
+
+    #[test]
+    fn bom_takes_precedence() {
+        let (text, encoding) = decode(&utf16(PLAYLIST, false), Some("text/plain; charset=gbk"));
+        assert_eq!(encoding, UTF_16LE);
+        assert_eq!(text, PLAYLIST);
+
+        let (text, encoding) = decode(&utf16(PLAYLIST, true), None);
+        assert_eq!(encoding, UTF_16BE);
+        assert_eq!(text, PLAYLIST);
+
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice(PLAYLIST.as_bytes());
+        let (text, encoding) = decode(&bytes, None);
+        assert_eq!(encoding, UTF_8);
+        assert_eq!(text, PLAYLIST);
+    }
+
+    #[test]
+    fn ascii_and_utf8_stay_utf8() {
+        assert_eq!(decode(b"#EXTM3U\n", None), ("#EXTM3U\n".to_string(), UTF_8));
+        assert_eq!(decode(PLAYLIST.as_bytes(), None), (PLAYLIST.to_string(), UTF_8));
+    }
+
+    #[test]
+    fn line_decoder_buffers_until_encoding_is_known() {
+        let (bytes, _, _) = GBK.encode(PLAYLIST);
+        let mut decoder = LineDecoder::new(None);
+        let mut lines: Vec<String> = Vec::new();
+        for line in bytes.split(|b| *b == b'\n') {
+            decoder.push(line, &mut |text| lines.push(text.to_string()));
+            // 第一行是 ASCII，不需要等待
+            assert_eq!(lines[0], "#EXTM3U");
+        }
+        assert_eq!(lines.len(), 1);
+        decoder.finish(&mut |text| lines.push(text.to_string()));
+
+        assert_eq!(decoder.encoding(), GBK);
+        assert_eq!(lines.join("\n"), PLAYLIST);
+    }
+}
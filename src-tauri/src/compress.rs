@@ -0,0 +1,460 @@
+use flate2::write::MultiGzDecoder;
+use std::io::{self, Cursor, Read, Write};
+use tracing::{debug, info, warn};
+
+/// 判断格式需要的字节数（tar 的 `ustar` 标记位于第 257 字节）
+const SNIFF_LEN: usize = 512;
+
+/// 最多解开的压缩层数（例如 `.tar.gz` 为两层）
+const MAX_DEPTH: usize = 4;
+
+/// 压缩数据和解压结果的最大大小，防止压缩炸弹；需要完整缓存的压缩包同时在内存中保留两者，
+/// 播放列表和节目单远小于这个大小
+const MAX_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 压缩包中优先选取的文件类型：播放列表、TVBox 配置和 XMLTV 节目单
+const ENTRY_EXTENSIONS: &[&str] = &["m3u", "m3u8", "txt", "json", "xml"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Gzip,
+    Zip,
+    Bzip2,
+    Xz,
+    Tar,
+    SevenZip,
+    Rar,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Gzip => "gzip",
+            Format::Zip => "zip",
+            Format::Bzip2 => "bzip2",
+            Format::Xz => "xz",
+            Format::Tar => "tar",
+            Format::SevenZip => "7z",
+            Format::Rar => "rar",
+        }
+    }
+
+    /// 按文件头识别
+    fn from_magic(head: &[u8]) -> Option<Format> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Format::Gzip)
+        } else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(Format::Zip)
+        } else if head.len() >= 10
+            && head.starts_with(b"BZh")
+            && (b'1'..=b'9').contains(&head[3])
+            && (head[4..10] == [0x31, 0x41, 0x59, 0x26, 0x53, 0x59] || head[4..10] == [0x17, 0x72, 0x45, 0x38, 0x50, 0x90])
+        {
+            Some(Format::Bzip2)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Format::Xz)
+        } else if head.starts_with(&[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c]) {
+            Some(Format::SevenZip)
+        } else if head.starts_with(b"Rar!\x1a\x07") {
+            Some(Format::Rar)
+        } else if head.get(257..262) == Some(b"ustar") {
+            Some(Format::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// 按扩展名识别
+    fn from_extension(name: &str) -> Option<Format> {
+        match extension(name).as_str() {
+            "gz" | "tgz" => Some(Format::Gzip),
+            "zip" => Some(Format::Zip),
+            "bz2" | "tbz2" => Some(Format::Bzip2),
+            "xz" | "txz" => Some(Format::Xz),
+            "tar" => Some(Format::Tar),
+            "7z" => Some(Format::SevenZip),
+            "rar" => Some(Format::Rar),
+            _ => None,
+        }
+    }
+
+    /// 文件头优先；扩展名只用于没有 `ustar` 标记的旧式 tar
+    ///
+    /// 扩展名是压缩格式但内容未压缩时（例如服务器已经按 Content-Encoding 解压）按原文处理。
+    fn detect(head: &[u8], name: &str) -> Option<Format> {
+        let format = Format::from_magic(head);
+        let by_extension = Format::from_extension(name);
+        if format.is_none() && by_extension == Some(Format::Tar) {
+            return by_extension;
+        }
+        if format.is_none() && by_extension.is_some() {
+            debug!("扩展名为压缩格式但内容未压缩，按原文处理: {}", name);
+        }
+        format
+    }
+}
+
+/// 去掉查询参数后的小写扩展名
+fn extension(name: &str) -> String {
+    let path = name.split(['?', '#']).next().unwrap_or(name);
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((_, ext)) => ext.to_ascii_lowercase(),
+        None => String::new(),
+    }
+}
+
+/// 解压一层后的文件名：`a.m3u.gz` → `a.m3u`，`a.tgz` → `a.tar`
+fn inner_name(name: &str) -> String {
+    let path = name.split(['?', '#']).next().unwrap_or(name);
+    let Some((stem, ext)) = path.rsplit_once('.') else {
+        return path.to_string();
+    };
+    match ext.to_ascii_lowercase().as_str() {
+        "tgz" | "tbz2" | "txz" => format!("{}.tar", stem),
+        "gz" | "bz2" | "xz" | "zip" => stem.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// 按文件头或扩展名识别并解开压缩内容（gzip、zip、bzip2、xz、tar，可以嵌套），未压缩的内容原样返回
+///
+/// 压缩包中取第一个播放列表 / 节目单文件，没有时取第一个文件。`name` 为 URL 或文件路径。
+pub fn decompress(bytes: Vec<u8>, name: &str) -> Result<Vec<u8>, String> {
+    if Format::detect(&bytes, name).is_none() {
+        return Ok(bytes);
+    }
+    let mut decompressor = Decompressor::new(name);
+    let mut output = decompressor.push(&bytes)?;
+    output.extend(decompressor.finish()?);
+    Ok(output)
+}
+
+enum State {
+    /// 还没收到足够判断格式的字节
+    Sniffing(Vec<u8>),
+    Plain,
+    /// gzip 可以边下载边解压，解压结果交给下一层继续识别
+    Gzip(Box<MultiGzDecoder<LimitedWriter>>, Box<Decompressor>),
+    /// 其他格式需要完整内容才能解开
+    Buffered(Format, Vec<u8>),
+}
+
+/// 流式解压：按块输入下载的原始字节，输出解压后的内容
+///
+/// gzip 边下载边解压；zip、bzip2、xz、tar 先缓存，在 `finish` 时一次性解开。
+pub struct Decompressor {
+    name: String,
+    depth: usize,
+    state: State,
+}
+
+impl Decompressor {
+    pub fn new(name: &str) -> Decompressor {
+        Decompressor::nested(name.to_string(), 0)
+    }
+
+    fn nested(name: String, depth: usize) -> Decompressor {
+        Decompressor {
+            name,
+            depth,
+            state: State::Sniffing(Vec::new()),
+        }
+    }
+
+    /// 输入一块原始数据，返回目前可以输出的解压内容
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<u8>, String> {
+        match &mut self.state {
+            State::Plain => Ok(chunk.to_vec()),
+            State::Sniffing(head) => {
+                head.extend_from_slice(chunk);
+                if head.len() < SNIFF_LEN {
+                    return Ok(Vec::new());
+                }
+                let head = std::mem::take(head);
+                self.start(&head)?;
+                self.push(&head)
+            }
+            State::Gzip(decoder, inner) => {
+                decoder.write_all(chunk).map_err(|e| format!("gzip 解压失败: {}", e))?;
+                let output = decoder.get_mut().take();
+                inner.push(&output)
+            }
+            State::Buffered(_, data) => {
+                data.extend_from_slice(chunk);
+                if data.len() as u64 > MAX_SIZE {
+                    return Err("压缩文件过大".to_string());
+                }
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// 输入结束，返回剩余的解压内容
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        if let State::Sniffing(head) = &mut self.state {
+            let head = std::mem::take(head);
+            self.start(&head)?;
+            let mut output = self.push(&head)?;
+            output.extend(self.finish()?);
+            return Ok(output);
+        }
+
+        match self.state {
+            State::Sniffing(_) => unreachable!(),
+            State::Plain => Ok(Vec::new()),
+            State::Gzip(decoder, mut inner) => {
+                let rest = decoder.finish().map_err(|e| format!("gzip 解压失败: {}", e))?.take();
+                let mut output = inner.push(&rest)?;
+                output.extend(inner.finish()?);
+                Ok(output)
+            }
+            State::Buffered(format, data) => {
+                let (data, name) = extract(format, data, &self.name)?;
+                info!("解压 {} 完成，解压后 {} 字节", format.name(), data.len());
+                let mut inner = Decompressor::nested(name, self.depth + 1);
+                let mut output = inner.push(&data)?;
+                output.extend(inner.finish()?);
+                Ok(output)
+            }
+        }
+    }
+
+    fn start(&mut self, head: &[u8]) -> Result<(), String> {
+        let Some(format) = Format::detect(head, &self.name) else {
+            self.state = State::Plain;
+            return Ok(());
+        };
+        if self.depth >= MAX_DEPTH {
+            return Err("压缩嵌套层数过多".to_string());
+        }
+
+        info!("检测到 {} 压缩内容: {}", format.name(), crate::redact_url(&self.name));
+        self.state = match format {
+            Format::SevenZip | Format::Rar => return Err(format!("不支持 {} 压缩格式，请先解压", format.name())),
+            Format::Gzip => State::Gzip(
+                Box::new(MultiGzDecoder::new(LimitedWriter::new(MAX_SIZE))),
+                Box::new(Decompressor::nested(inner_name(&self.name), self.depth + 1)),
+            ),
+            format => State::Buffered(format, Vec::new()),
+        };
+        Ok(())
+    }
+}
+
+/// 解开完整的压缩内容，返回内容和其中的文件名（用于识别下一层）
+fn extract(format: Format, data: Vec<u8>, name: &str) -> Result<(Vec<u8>, String), String> {
+    match format {
+        Format::Bzip2 => {
+            let mut output = Vec::new();
+            bzip2::read::MultiBzDecoder::new(&data[..])
+                .take(MAX_SIZE + 1)
+                .read_to_end(&mut output)
+                .map_err(|e| format!("bzip2 解压失败: {}", e))?;
+            check_size(&output)?;
+            Ok((output, inner_name(name)))
+        }
+        Format::Xz => Ok((xz_decompress(&data, MAX_SIZE)?, inner_name(name))),
+        Format::Zip => extract_zip(data),
+        Format::Tar => extract_tar(&data),
+        Format::Gzip | Format::SevenZip | Format::Rar => unreachable!(),
+    }
+}
+
+/// 解压 xz，解压后超过 `limit` 字节时中止
+fn xz_decompress(data: &[u8], limit: u64) -> Result<Vec<u8>, String> {
+    let mut output = LimitedWriter::new(limit);
+    if let Err(e) = lzma_rs::xz_decompress(&mut &data[..], &mut output) {
+        if output.exceeded() {
+            return Err("解压后的内容过大".to_string());
+        }
+        return Err(format!("xz 解压失败: {}", e));
+    }
+    Ok(output.take())
+}
+
+/// 解压输出的缓冲区，累计写入超过上限时报错，防止压缩炸弹在检查大小前占满内存
+///
+/// `take` 取出已写入的内容后仍然累计计数。
+struct LimitedWriter {
+    buffer: Vec<u8>,
+    written: u64,
+    limit: u64,
+}
+
+impl LimitedWriter {
+    fn new(limit: u64) -> LimitedWriter {
+        LimitedWriter {
+            buffer: Vec::new(),
+            written: 0,
+            limit,
+        }
+    }
+
+    fn exceeded(&self) -> bool {
+        self.written > self.limit
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len() as u64;
+        if self.exceeded() {
+            return Err(io::Error::other("解压后的内容过大"));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn check_size(output: &[u8]) -> Result<(), String> {
+    if output.len() as u64 > MAX_SIZE {
+        return Err("解压后的内容过大".to_string());
+    }
+    Ok(())
+}
+
+fn is_wanted_entry(name: &str) -> bool {
+    ENTRY_EXTENSIONS.contains(&extension(name).as_str())
+}
+
+/// macOS 打包时附带的元数据文件
+fn is_metadata_entry(name: &str) -> bool {
+    name.starts_with("__MACOSX/") || name.rsplit('/').next().is_some_and(|f| f.starts_with("._"))
+}
+
+fn extract_zip(data: Vec<u8>) -> Result<(Vec<u8>, String), String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|e| format!("无法读取 zip 文件: {}", e))?;
+
+    let names: Vec<String> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && !is_metadata_entry(name))
+        .map(str::to_string)
+        .collect();
+    let Some(entry_name) = names.iter().find(|n| is_wanted_entry(n)).or(names.first()).cloned() else {
+        return Err("zip 文件中没有文件".to_string());
+    };
+    if names.len() > 1 {
+        warn!("zip 文件包含 {} 个文件，使用 {}", names.len(), entry_name);
+    }
+
+    let mut output = Vec::new();
+    archive
+        .by_name(&entry_name)
+        .map_err(|e| format!("无法读取 zip 中的 {}: {}", entry_name, e))?
+        .take(MAX_SIZE + 1)
+        .read_to_end(&mut output)
+        .map_err(|e| format!("zip 解压失败: {}", e))?;
+    check_size(&output)?;
+    Ok((output, entry_name))
+}
+
+fn extract_tar(data: &[u8]) -> Result<(Vec<u8>, String), String> {
+    let mut archive = tar::Archive::new(data);
+    let entries = archive.entries().map_err(|e| format!("无法读取 tar 文件: {}", e))?;
+
+    let mut fallback: Option<(Vec<u8>, String)> = None;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("无法读取 tar 文件: {}", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_name = entry.path().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+        if is_metadata_entry(&entry_name) {
+            continue;
+        }
+
+        let wanted = is_wanted_entry(&entry_name);
+        if !wanted && fallback.is_some() {
+            continue;
+        }
+        let mut output = Vec::new();
+        entry
+            .by_ref()
+            .take(MAX_SIZE + 1)
+            .read_to_end(&mut output)
+            .map_err(|e| format!("tar 解压失败: {}", e))?;
+        check_size(&output)?;
+        if wanted {
+            return Ok((output, entry_name));
+        }
+        fallback = Some((output, entry_name));
+    }
+
+    fallback.ok_or_else(|| "tar 文件中没有文件".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    const PLAYLIST: &[u8] = b"#EXTM3U\n#EXTINF:-1,CCTV1\nhttp://a.com/1.m3u8\n";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn xz(data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        lzma_rs::xz_compress(&mut &data[..], &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn plain_content_is_returned_as_is() {
+        assert_eq!(decompress(PLAYLIST.to_vec(), "a.m3u.gz").unwrap(), PLAYLIST);
+    }
+
+    #[test]
+    fn gzip_is_decompressed_in_chunks() {
+        let compressed = gzip(PLAYLIST);
+        let mut decompressor = Decompressor::new("a.m3u.gz");
+        let mut output = Vec::new();
+        for chunk in compressed.chunks(7) {
+            output.extend(decompressor.push(chunk).unwrap());
+        }
+        output.extend(decompressor.finish().unwrap());
+        assert_eq!(output, PLAYLIST);
+    }
+
+    #[test]
+    fn xz_is_decompressed() {
+        assert_eq!(decompress(xz(PLAYLIST), "epg.xml.xz").unwrap(), PLAYLIST);
+    }
+
+    #[test]
+    fn xz_stops_at_size_limit() {
+        let compressed = xz(&vec![b'a'; 64 * 1024]);
+        assert_eq!(xz_decompress(&compressed, 64 * 1024).unwrap().len(), 64 * 1024);
+        assert_eq!(xz_decompress(&compressed, 1024).unwrap_err(), "解压后的内容过大");
+    }
+
+    #[test]
+    fn gzip_stops_at_size_limit() {
+        let compressed = gzip(&vec![b'a'; 64 * 1024]);
+        let mut decoder = MultiGzDecoder::new(LimitedWriter::new(1024));
+        let mut result = Ok(());
+        for chunk in compressed.chunks(16) {
+            result = decoder.write_all(chunk);
+            // 已输出的内容被取走后仍然累计计数
+            decoder.get_mut().take();
+            if result.is_err() {
+                break;
+            }
+        }
+        assert!(result.is_err());
+        assert!(decoder.get_ref().exceeded());
+    }
+}
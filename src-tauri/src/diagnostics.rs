@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// 报告中最多保留的问题条数，超出的只计数
+const MAX_ISSUES: usize = 500;
+
+/// 解析问题的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// 条目被跳过，没有导入（例如 `#EXTINF` 后面没有地址）
+    Skipped,
+    /// 条目格式不规范，但仍然导入
+    Malformed,
+    /// 地址与前面的频道重复
+    DuplicateUrl,
+    /// 频道没有名称，使用了默认名称
+    MissingName,
+}
+
+impl IssueKind {
+    fn label(self) -> &'static str {
+        match self {
+            IssueKind::Skipped => "跳过",
+            IssueKind::Malformed => "格式异常",
+            IssueKind::DuplicateUrl => "重复地址",
+            IssueKind::MissingName => "缺少名称",
+        }
+    }
+}
+
+/// 单个解析问题
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseIssue {
+    /// 行号（从 1 开始）
+    pub line: usize,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+/// 不支持的指令，按指令名汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectiveUsage {
+    /// 指令名，例如 `#EXTIMG`
+    pub name: String,
+    /// 第一次出现的行号
+    pub first_line: usize,
+    pub count: usize,
+}
+
+/// 播放列表解析报告：记录被跳过或不规范的条目，导入时不再静默丢弃
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParseReport {
+    /// 解析的总行数
+    pub lines: usize,
+    /// 按行号排列的问题（最多保留 `MAX_ISSUES` 条）
+    pub issues: Vec<ParseIssue>,
+    /// 各类问题的总数
+    pub counts: BTreeMap<IssueKind, usize>,
+    pub unsupported_directives: Vec<DirectiveUsage>,
+    /// 地址 -> 第一次出现的行号，用于检测重复地址
+    #[serde(skip)]
+    seen_urls: HashMap<String, usize>,
+}
+
+impl ParseReport {
+    pub fn add(&mut self, line: usize, kind: IssueKind, message: impl Into<String>) {
+        *self.counts.entry(kind).or_default() += 1;
+        if self.issues.len() < MAX_ISSUES {
+            self.issues.push(ParseIssue {
+                line,
+                kind,
+                message: message.into(),
+            });
+        }
+    }
+
+    /// 记录频道地址，和前面的频道重复时报告
+    pub fn check_duplicate_url(&mut self, line: usize, url: &str) {
+        match self.seen_urls.get(url) {
+            Some(&first_line) => self.add(line, IssueKind::DuplicateUrl, format!("地址与第 {} 行重复: {}", first_line, url)),
+            None => {
+                self.seen_urls.insert(url.to_string(), line);
+            }
+        }
+    }
+
+    pub fn unsupported_directive(&mut self, line: usize, name: &str) {
+        match self.unsupported_directives.iter_mut().find(|d| d.name == name) {
+            Some(usage) => usage.count += 1,
+            None => self.unsupported_directives.push(DirectiveUsage {
+                name: name.to_string(),
+                first_line: line,
+                count: 1,
+            }),
+        }
+    }
+
+    /// 解析结束，记录总行数并释放去重用的索引（跳过的条目在读到下一行时才确定，需要重新排序）
+    pub fn finish(&mut self, lines: usize) {
+        self.lines = lines;
+        self.issues.sort_by_key(|issue| issue.line);
+        self.seen_urls = HashMap::new();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty() && self.unsupported_directives.is_empty()
+    }
+
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// 一行摘要，用于日志和错误信息
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = [IssueKind::Skipped, IssueKind::Malformed, IssueKind::DuplicateUrl, IssueKind::MissingName]
+            .into_iter()
+            .filter(|kind| self.count(*kind) > 0)
+            .map(|kind| format!("{} {} 处", kind.label(), self.count(kind)))
+            .collect();
+        if !self.unsupported_directives.is_empty() {
+            let names: Vec<&str> = self.unsupported_directives.iter().map(|d| d.name.as_str()).collect();
+            parts.push(format!("不支持的指令 {}", names.join(" ")));
+        }
+        if let Some(first) = self.issues.first() {
+            parts.push(format!("第 {} 行: {}", first.line, first.message));
+        }
+        parts.join("，")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_issues_beyond_the_cap() {
+        let mut report = ParseReport::default();
+        for line in 1..=MAX_ISSUES + 20 {
+            report.add(line, IssueKind::Malformed, "格式异常");
+        }
+        report.add(1000, IssueKind::Skipped, "跳过");
+        assert_eq!(report.issues.len(), MAX_ISSUES);
+        assert_eq!(report.count(IssueKind::Malformed), MAX_ISSUES + 20);
+        assert_eq!(report.count(IssueKind::Skipped), 1);
+        assert!(report.summary().starts_with("跳过 1 处，格式异常 520 处"));
+    }
+
+    #[test]
+    fn duplicate_urls_point_to_first_line() {
+        let mut report = ParseReport::default();
+        report.check_duplicate_url(2, "http://a/1");
+        report.check_duplicate_url(4, "http://a/2");
+        report.check_duplicate_url(6, "http://a/1");
+        report.check_duplicate_url(8, "http://a/1");
+        assert_eq!(report.count(IssueKind::DuplicateUrl), 2);
+        let issues: Vec<(usize, &str)> = report.issues.iter().map(|i| (i.line, i.message.as_str())).collect();
+        assert_eq!(issues, [(6, "地址与第 2 行重复: http://a/1"), (8, "地址与第 2 行重复: http://a/1")]);
+    }
+
+    #[test]
+    fn finish_sorts_issues_by_line() {
+        let mut report = ParseReport::default();
+        report.add(5, IssueKind::MissingName, "频道没有名称");
+        // 没有地址的条目在读到下一行时才报告
+        report.add(3, IssueKind::Skipped, "#EXTINF 后面没有播放地址: A");
+        report.add(9, IssueKind::Malformed, "地址缺少协议");
+        report.finish(12);
+        let lines: Vec<usize> = report.issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, [3, 5, 9]);
+        assert_eq!(report.lines, 12);
+        assert!(report.summary().ends_with("第 3 行: #EXTINF 后面没有播放地址: A"));
+    }
+
+    #[test]
+    fn unsupported_directives_are_grouped() {
+        let mut report = ParseReport::default();
+        assert!(report.is_empty());
+        report.unsupported_directive(3, "#EXTIMG");
+        report.unsupported_directive(7, "#EXTALB");
+        report.unsupported_directive(9, "#EXTIMG");
+        assert!(!report.is_empty());
+        let usages: Vec<(&str, usize, usize)> = report.unsupported_directives.iter().map(|d| (d.name.as_str(), d.first_line, d.count)).collect();
+        assert_eq!(usages, [("#EXTIMG", 3, 2), ("#EXTALB", 7, 1)]);
+        assert_eq!(report.summary(), "不支持的指令 #EXTIMG #EXTALB");
+    }
+}
@@ -0,0 +1,459 @@
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OnceCell, Semaphore};
+use tracing::{debug, info, warn};
+
+use crate::epg::Programme;
+
+/// DIYP 接口返回的时间为北京时间
+const PROVIDER_UTC_OFFSET_SECS: i32 = 8 * 3600;
+
+/// 查询成功的结果缓存时间
+const CACHE_TTL_SECS: i64 = 6 * 3600;
+
+/// 查询失败或没有节目时，过一段时间再重试
+const RETRY_AFTER_SECS: i64 = 10 * 60;
+
+/// 同时进行的请求数，避免一次查询几百个频道时压垮接口
+const MAX_CONCURRENT_REQUESTS: usize = 6;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// DIYP / 百川格式的节目单接口（`?ch=CCTV1&date=2026-10-18`）
+///
+/// 地址可以是带 `{name}` / `{date}` 占位符的模板（TVBox 配置的 `epg` 字段），
+/// 也可以是同时带 `ch=` 和 `date=` 参数的接口地址，查询时替换频道名和日期。
+/// 只有 `ch=` 的地址（`e.xml?ch=all`）按 XMLTV 处理。
+pub fn is_diyp_url(url: &str) -> bool {
+    url.contains("{name}") || url.contains("{date}") || (has_query_param(url, "ch") && has_query_param(url, "date"))
+}
+
+fn has_query_param(url: &str, name: &str) -> bool {
+    url.split_once('?')
+        .is_some_and(|(_, query)| query.split('&').any(|pair| pair.split('=').next() == Some(name)))
+}
+
+/// 生成某个频道某一天的查询地址
+pub fn request_url(template: &str, channel: &str, date: NaiveDate) -> String {
+    let date = date.format("%Y-%m-%d").to_string();
+    let name = urlencoding::encode(channel);
+    if template.contains("{name}") || template.contains("{date}") {
+        return template.replace("{name}", &name).replace("{date}", &date);
+    }
+
+    // 去掉原有的 ch / date 参数再重新添加
+    let (base, query) = template.split_once('?').unwrap_or((template, ""));
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| !matches!(pair.split('=').next(), Some("ch" | "date")))
+        .map(str::to_string)
+        .collect();
+    pairs.push(format!("ch={}", name));
+    pairs.push(format!("date={}", date));
+    format!("{}?{}", base, pairs.join("&"))
+}
+
+#[derive(Debug, Deserialize)]
+struct DiypResponse {
+    #[serde(default)]
+    epg_data: Vec<DiypProgramme>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiypProgramme {
+    start: String,
+    end: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    desc: String,
+}
+
+/// 解析接口返回的 JSON：时间为 `HH:MM`（当天）或 `YYYY-MM-DD HH:MM`，结束时间早于开始时间表示跨过午夜
+pub fn parse(body: &str, date: NaiveDate) -> Result<Vec<Programme>, String> {
+    let response: DiypResponse = serde_json::from_str(body).map_err(|e| format!("解析 DIYP 节目单失败: {}", e))?;
+    let offset = FixedOffset::east_opt(PROVIDER_UTC_OFFSET_SECS).expect("有效的时区偏移");
+
+    let mut programmes: Vec<Programme> = response
+        .epg_data
+        .into_iter()
+        .filter(|p| !p.title.trim().is_empty())
+        .filter_map(|p| {
+            let start = parse_time(&p.start, date)?;
+            let mut stop = parse_time(&p.end, date)?;
+            if stop <= start {
+                stop += chrono::Duration::days(1);
+            }
+            let timestamp = |t: NaiveDateTime| offset.from_local_datetime(&t).single().map(|t| t.timestamp());
+            Some(Programme {
+                start: timestamp(start)?,
+                stop: timestamp(stop)?,
+                title: p.title.trim().to_string(),
+                desc: Some(p.desc.trim().to_string()).filter(|d| !d.is_empty()),
+                ..Default::default()
+            })
+        })
+        .collect();
+    programmes.sort_by_key(|p| p.start);
+    programmes.dedup_by_key(|p| p.start);
+    Ok(programmes)
+}
+
+fn parse_time(value: &str, date: NaiveDate) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            ["%H:%M:%S", "%H:%M"]
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+                .map(|time| date.and_time(time))
+        })
+}
+
+/// 接口时区中包含时间段 `[start, end)` 的日期
+fn dates_between(start: i64, end: i64) -> Vec<NaiveDate> {
+    let offset = FixedOffset::east_opt(PROVIDER_UTC_OFFSET_SECS).expect("有效的时区偏移");
+    let date_of = |ts: i64| offset.timestamp_opt(ts, 0).single().map(|t| t.date_naive());
+    let (Some(first), Some(last)) = (date_of(start), date_of(end.max(start + 1) - 1)) else {
+        return Vec::new();
+    };
+    first.iter_days().take_while(|d| *d <= last).collect()
+}
+
+/// 下载一个频道一天的节目
+pub async fn fetch_day(client: &reqwest::Client, template: &str, channel: &str, date: NaiveDate) -> Result<Vec<Programme>, String> {
+    let url = request_url(template, channel, date);
+    debug!("查询 DIYP 节目单: {}", crate::redact_url(&url));
+    let response = client.get(&url).send().await.map_err(|e| format!("请求失败: {}", e.without_url()))?;
+    if !response.status().is_success() {
+        return Err(format!("请求失败: HTTP {}", response.status()));
+    }
+    let body = response.text().await.map_err(|e| format!("读取内容失败: {}", e.without_url()))?;
+    parse(&body, date)
+}
+
+struct CacheEntry {
+    template: String,
+    channel: String,
+    programmes: Arc<Vec<Programme>>,
+    /// 查询时间和过期时间（Unix 秒）
+    fetched_at: i64,
+    expires_at: i64,
+    error: Option<String>,
+}
+
+/// 一个 DIYP 接口的查询情况，根据缓存中的结果统计
+#[derive(Debug, Clone, Default)]
+pub struct DiypStats {
+    /// 最近一次查询成功的时间
+    pub fetched_at: Option<i64>,
+    /// 查询到节目的频道数
+    pub channel_count: usize,
+    pub programme_count: usize,
+    /// 最近一次查询失败的原因（之后没有查询成功过）
+    pub error: Option<String>,
+}
+
+/// 正在进行的一次查询，完成后所有等待者得到同一结果
+type PendingDay = OnceCell<Arc<Vec<Programme>>>;
+
+/// 按频道和日期缓存的 DIYP 查询结果，只在查询到时才请求接口
+pub struct DiypCache {
+    client: reqwest::Client,
+    /// 查询地址 -> 结果
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    /// 正在查询的地址，同一频道同一天的并发查询只请求一次
+    pending: Mutex<HashMap<String, Arc<PendingDay>>>,
+    requests: Semaphore,
+}
+
+impl DiypCache {
+    pub fn new() -> DiypCache {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_else(|e| {
+                warn!("创建 DIYP 客户端失败，使用默认设置: {}", e);
+                reqwest::Client::new()
+            });
+        DiypCache {
+            client,
+            entries: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            requests: Semaphore::new(MAX_CONCURRENT_REQUESTS),
+        }
+    }
+
+    /// 频道一天的节目；失败时返回空列表并在一段时间后重试
+    ///
+    /// 同一地址正在查询时等待那次查询的结果，不重复请求。
+    async fn day(&self, template: &str, channel: &str, date: NaiveDate) -> Arc<Vec<Programme>> {
+        let key = request_url(template, channel, date);
+        if let Some(programmes) = self.cached(&key) {
+            return programmes;
+        }
+
+        let cell = self.pending.lock().unwrap().entry(key.clone()).or_default().clone();
+        let programmes = cell.get_or_init(|| self.fetch(&key, template, channel, date)).await.clone();
+        let mut pending = self.pending.lock().unwrap();
+        if pending.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            pending.remove(&key);
+        }
+        programmes
+    }
+
+    /// 缓存中未过期的结果
+    fn cached(&self, key: &str) -> Option<Arc<Vec<Programme>>> {
+        let now = chrono::Utc::now().timestamp();
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|e| e.expires_at > now)
+            .map(|e| e.programmes.clone())
+    }
+
+    /// 请求接口并缓存结果
+    async fn fetch(&self, key: &str, template: &str, channel: &str, date: NaiveDate) -> Arc<Vec<Programme>> {
+        // 前一次查询可能在检查缓存之后刚刚完成
+        if let Some(programmes) = self.cached(key) {
+            return programmes;
+        }
+
+        let result = {
+            let _permit = self.requests.acquire().await.expect("信号量不会关闭");
+            fetch_day(&self.client, template, channel, date).await
+        };
+        let (programmes, ttl, error) = match result {
+            Ok(programmes) if !programmes.is_empty() => {
+                info!("DIYP 节目单: {} {} 共 {} 个节目", channel, date, programmes.len());
+                (programmes, CACHE_TTL_SECS, None)
+            }
+            Ok(programmes) => {
+                debug!("DIYP 接口没有 {} {} 的节目", channel, date);
+                (programmes, RETRY_AFTER_SECS, None)
+            }
+            Err(e) => {
+                warn!("查询 DIYP 节目单失败 {} {}: {}", channel, date, e);
+                (Vec::new(), RETRY_AFTER_SECS, Some(e))
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let programmes = Arc::new(programmes);
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| e.expires_at > now);
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                template: template.to_string(),
+                channel: channel.to_string(),
+                programmes: programmes.clone(),
+                fetched_at: now,
+                expires_at: now + ttl,
+                error,
+            },
+        );
+        programmes
+    }
+
+    /// 接口地址的查询情况，只统计还在缓存中的结果
+    pub fn stats(&self, template: &str) -> DiypStats {
+        let entries = self.entries.lock().unwrap();
+        let entries: Vec<&CacheEntry> = entries.values().filter(|e| e.template == template).collect();
+        let fetched_at = entries.iter().filter(|e| e.error.is_none()).map(|e| e.fetched_at).max();
+        let error = entries
+            .iter()
+            .filter(|e| e.error.is_some() && fetched_at.is_none_or(|t| e.fetched_at > t))
+            .max_by_key(|e| e.fetched_at)
+            .and_then(|e| e.error.clone());
+        let channels: HashSet<&str> = entries.iter().filter(|e| !e.programmes.is_empty()).map(|e| e.channel.as_str()).collect();
+        DiypStats {
+            fetched_at,
+            channel_count: channels.len(),
+            programme_count: entries.iter().map(|e| e.programmes.len()).sum(),
+            error,
+        }
+    }
+
+    /// 频道在时间段 `[start, end)` 内的节目，按需查询涉及的每一天
+    pub async fn programmes(&self, template: &str, channel: &str, start: i64, end: i64) -> Vec<Programme> {
+        let mut programmes: Vec<Programme> = Vec::new();
+        for date in dates_between(start, end) {
+            let day = self.day(template, channel, date).await;
+            programmes.extend(day.iter().filter(|p| p.stop > start && p.start < end).cloned());
+        }
+        programmes.sort_by_key(|p| p.start);
+        programmes.dedup_by_key(|p| p.start);
+        programmes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Query, State};
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    /// 北京时间转为 Unix 时间戳
+    fn beijing(value: &str) -> i64 {
+        let time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        time.and_utc().timestamp() - i64::from(PROVIDER_UTC_OFFSET_SECS)
+    }
+
+    #[test]
+    fn detects_diyp_urls() {
+        assert!(is_diyp_url("http://epg.example.com/?ch={name}&date={date}"));
+        assert!(is_diyp_url("http://epg.example.com/api/{name}/{date}.json"));
+        assert!(is_diyp_url("http://epg.example.com/api/?ch=CCTV1&date=2026-10-18"));
+        assert!(!is_diyp_url("http://epg.example.com/e.xml?ch=all"));
+        assert!(!is_diyp_url("http://epg.example.com/e.xml.gz"));
+        assert!(!is_diyp_url("http://epg.example.com/e.xml?channel=1&date=2026-10-18"));
+    }
+
+    #[test]
+    fn builds_request_urls() {
+        let day = date("2026-10-18");
+        assert_eq!(
+            request_url("http://a.com/?ch={name}&date={date}", "CCTV5+", day),
+            "http://a.com/?ch=CCTV5%2B&date=2026-10-18"
+        );
+        assert_eq!(
+            request_url("http://a.com/api/?token=1&ch=CCTV1&date=2020-01-01", "湖南卫视", day),
+            "http://a.com/api/?token=1&ch=%E6%B9%96%E5%8D%97%E5%8D%AB%E8%A7%86&date=2026-10-18"
+        );
+        assert_eq!(request_url("http://a.com/api", "CCTV1", day), "http://a.com/api?ch=CCTV1&date=2026-10-18");
+    }
+
+    #[test]
+    fn parses_time_only_programmes() {
+        let body = r#"{"epg_data": [
+            {"start": "20:00", "end": "21:00", "title": "新闻联播", "desc": " 要闻 "},
+            {"start": "19:00", "end": "20:00", "title": "天气预报", "desc": ""},
+            {"start": "21:00", "end": "22:00", "title": " "}
+        ]}"#;
+        let programmes = parse(body, date("2026-10-18")).unwrap();
+        let titles: Vec<&str> = programmes.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["天气预报", "新闻联播"]);
+        assert_eq!(programmes[1].start, beijing("2026-10-18 20:00"));
+        assert_eq!(programmes[1].stop, beijing("2026-10-18 21:00"));
+        assert_eq!(programmes[1].desc.as_deref(), Some("要闻"));
+        assert_eq!(programmes[0].desc, None);
+    }
+
+    #[test]
+    fn parses_full_date_times() {
+        let body = r#"{"epg_data": [{"start": "2026-10-17 23:30", "end": "2026-10-18 00:30:00", "title": "午夜剧场"}]}"#;
+        let programmes = parse(body, date("2026-10-18")).unwrap();
+        assert_eq!(programmes[0].start, beijing("2026-10-17 23:30"));
+        assert_eq!(programmes[0].stop, beijing("2026-10-18 00:30"));
+    }
+
+    #[test]
+    fn end_before_start_rolls_over_midnight() {
+        let body = r#"{"epg_data": [{"start": "23:30", "end": "00:30", "title": "晚间新闻"}]}"#;
+        let programmes = parse(body, date("2026-10-18")).unwrap();
+        assert_eq!(programmes[0].start, beijing("2026-10-18 23:30"));
+        assert_eq!(programmes[0].stop, beijing("2026-10-19 00:30"));
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(parse("<html>", date("2026-10-18")).is_err());
+        assert!(parse("{}", date("2026-10-18")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dates_in_provider_time_zone() {
+        assert_eq!(
+            dates_between(beijing("2026-10-18 20:00"), beijing("2026-10-19 02:00")),
+            [date("2026-10-18"), date("2026-10-19")]
+        );
+        // 结束时间是开区间
+        assert_eq!(dates_between(beijing("2026-10-18 00:00"), beijing("2026-10-19 00:00")), [date("2026-10-18")]);
+        assert_eq!(dates_between(beijing("2026-10-18 12:00"), beijing("2026-10-18 12:00")), [date("2026-10-18")]);
+    }
+
+    /// 模拟 DIYP 接口：每天两个节目，下午的节目跨过午夜；记录请求次数
+    async fn diyp_api(State(requests): State<Arc<AtomicUsize>>, Query(params): Query<HashMap<String, String>>) -> String {
+        requests.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let channel = params.get("ch").cloned().unwrap_or_default();
+        format!(
+            r#"{{"epg_data": [{{"start": "00:00", "end": "12:00", "title": "{0} 上午"}}, {{"start": "12:00", "end": "00:00", "title": "{0} 下午"}}]}}"#,
+            channel
+        )
+    }
+
+    async fn start_server() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route("/epg", get(diyp_api)).with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/epg?ch={{name}}&date={{date}}", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn programmes_are_fetched_once_per_day_and_cached() {
+        let (template, requests) = start_server().await;
+        let cache = DiypCache::new();
+        let (start, end) = (beijing("2026-10-18 20:00"), beijing("2026-10-19 02:00"));
+
+        let programmes = cache.programmes(&template, "CCTV1", start, end).await;
+        let titles: Vec<&str> = programmes.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["CCTV1 下午", "CCTV1 上午"]);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let again = cache.programmes(&template, "CCTV1", start, end).await;
+        assert_eq!(again, programmes);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let stats = cache.stats(&template);
+        assert_eq!(stats.channel_count, 1);
+        assert_eq!(stats.programme_count, 4);
+        assert!(stats.fetched_at.is_some());
+        assert_eq!(stats.error, None);
+    }
+
+    #[tokio::test]
+    async fn concurrent_queries_share_one_request() {
+        let (template, requests) = start_server().await;
+        let cache = DiypCache::new();
+        let (start, end) = (beijing("2026-10-18 08:00"), beijing("2026-10-18 20:00"));
+
+        let (first, second, third) = tokio::join!(
+            cache.programmes(&template, "CCTV1", start, end),
+            cache.programmes(&template, "CCTV1", start, end),
+            cache.programmes(&template, "CCTV1", start, end),
+        );
+        assert_eq!(first.len(), 2);
+        assert_eq!(first, second);
+        assert_eq!(first, third);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(cache.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_queries_are_reported() {
+        let cache = DiypCache::new();
+        let template = "http://127.0.0.1:1/epg?ch={name}&date={date}";
+        let programmes = cache.programmes(template, "CCTV1", beijing("2026-10-18 08:00"), beijing("2026-10-18 09:00")).await;
+        assert!(programmes.is_empty());
+        let stats = cache.stats(template);
+        assert_eq!(stats.fetched_at, None);
+        assert!(stats.error.is_some());
+    }
+}
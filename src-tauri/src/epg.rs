@@ -0,0 +1,1064 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::channel_name::{self, ChannelAliases};
+use crate::compress::Decompressor;
+use crate::http_settings::SourceHttpSettings;
+use crate::{diyp, redact_url, xmltv, AppState, Channel, HttpValidators, Source};
+
+/// 节目单数据保存在数据目录下的子目录中，每个地址一个文件
+const EPG_DIR: &str = "epg";
+
+/// 各节目单地址的下载状态
+const FEEDS_FILE: &str = "feeds.json";
+
+/// 全局节目单设置
+const SETTINGS_FILE: &str = "settings.json";
+
+/// 节目单超过这个时间后重新下载（服务器支持时为条件请求）
+const MAX_AGE_SECS: i64 = 12 * 3600;
+
+/// 后台检查节目单是否需要更新的间隔
+const SYNC_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 查询下一个节目时最多往后查找的时间
+const NEXT_LOOKAHEAD_SECS: i64 = 6 * 3600;
+
+/// 节目表一次最多查询的时间段（DIYP 接口按天查询，时间段过长会发出大量请求）
+const MAX_GRID_SECS: i64 = 3 * 24 * 3600;
+
+/// 下载和解析之间缓冲的数据块数量
+const CHUNK_BUFFER: usize = 16;
+
+/// 节目单更新后发送给前端的事件
+pub const EPG_EVENT: &str = "epg-updated";
+
+/// 节目单中的频道（`<channel>`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EpgChannel {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub display_names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// 一个节目（`<programme>`），时间为 Unix 时间戳（秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Programme {
+    pub start: i64,
+    pub stop: i64,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// 集数（优先使用 `system="onscreen"` 的写法）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// 一个节目单地址的全部数据，频道索引不保存，加载后重建
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Guide {
+    pub channels: Vec<EpgChannel>,
+    /// 节目单频道 ID -> 按开始时间排序的节目
+    pub programmes: HashMap<String, Vec<Programme>>,
+    /// 小写的频道 ID -> 频道 ID
+    #[serde(skip)]
+    ids: HashMap<String, String>,
+    /// 规范化的频道名称（display-name，其次是 ID）-> 频道 ID
+    #[serde(skip)]
+    names: HashMap<String, String>,
+}
+
+impl Guide {
+    pub fn new(channels: Vec<EpgChannel>, programmes: HashMap<String, Vec<Programme>>) -> Guide {
+        let mut guide = Guide {
+            channels,
+            programmes,
+            ..Default::default()
+        };
+        guide.build_index();
+        guide
+    }
+
+    fn build_index(&mut self) {
+        self.ids.clear();
+        self.names.clear();
+        let ids = self.channels.iter().map(|c| &c.id).chain(self.programmes.keys());
+        for id in ids {
+            self.ids.entry(id_key(id)).or_insert_with(|| id.clone());
+        }
+        for channel in &self.channels {
+            for name in &channel.display_names {
+                self.names.entry(channel_name::match_key(name)).or_insert_with(|| channel.id.clone());
+            }
+        }
+        // 很多节目单直接用频道名作为 ID（`<channel id="CCTV1">`）
+        for id in self.ids.values() {
+            self.names.entry(channel_name::match_key(id)).or_insert_with(|| id.clone());
+        }
+    }
+
+    pub fn programme_count(&self) -> usize {
+        self.programmes.values().map(Vec::len).sum()
+    }
+
+    /// 按 tvg-id、tvg-name、频道名的顺序匹配节目单中的频道，返回节目单频道 ID
+    ///
+    /// tvg-id 先与频道 ID 比较（不区分大小写），其余都按规范化的名称（见 [`channel_name`]）
+    /// 与 display-name 比较，频道名有别名时先用别名。
+    pub fn resolve(&self, channel: &Channel, aliases: &ChannelAliases) -> Option<&str> {
+        let by_name = |name: &str| aliases.match_keys(name).iter().find_map(|key| self.names.get(key));
+        channel
+            .tvg_id
+            .as_deref()
+            .and_then(|id| self.ids.get(&id_key(id)).or_else(|| by_name(id)))
+            .or_else(|| channel.tvg_name.as_deref().and_then(by_name))
+            .or_else(|| by_name(&channel.name))
+            .map(String::as_str)
+    }
+
+    /// 频道的全部节目，没有匹配到或没有节目时返回 None
+    pub fn programmes_for(&self, channel: &Channel, aliases: &ChannelAliases) -> Option<&[Programme]> {
+        let id = self.resolve(channel, aliases)?;
+        self.programmes.get(id).map(Vec::as_slice).filter(|p| !p.is_empty())
+    }
+}
+
+fn id_key(id: &str) -> String {
+    id.trim().to_lowercase()
+}
+
+/// 与时间段 `[start, end)` 有重叠的节目（节目按开始时间排序，二分查找）
+fn overlapping(programmes: &[Programme], start: i64, end: i64) -> &[Programme] {
+    // 开始时间不晚于 start 的最后一个节目可能还在播放
+    let first = programmes.partition_point(|p| p.start <= start).saturating_sub(1);
+    let first = first + programmes[first..].iter().take_while(|p| p.stop <= start).count();
+    let last = programmes.partition_point(|p| p.start < end).max(first);
+    &programmes[first..last]
+}
+
+/// 频道的时移（tvg-shift，小时）换算为秒
+fn shift_secs(channel: &Channel) -> i64 {
+    channel.tvg_shift.map(|hours| (f64::from(hours) * 3600.0).round() as i64).unwrap_or(0)
+}
+
+impl Programme {
+    fn shifted(&self, secs: i64) -> Programme {
+        Programme {
+            start: self.start + secs,
+            stop: self.stop + secs,
+            ..self.clone()
+        }
+    }
+}
+
+/// 频道的当前节目和下一个节目
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NowNext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub now: Option<Programme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<Programme>,
+}
+
+impl NowNext {
+    /// 从按开始时间排序的节目中找出 `at` 时刻的当前节目和下一个节目
+    fn at(programmes: &[Programme], at: i64) -> NowNext {
+        let upcoming = programmes.partition_point(|p| p.start <= at);
+        NowNext {
+            now: upcoming.checked_sub(1).map(|i| &programmes[i]).filter(|p| p.stop > at).cloned(),
+            next: programmes.get(upcoming).cloned(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.now.is_none() && self.next.is_none()
+    }
+}
+
+/// 节目表中的一行：一个频道在时间段内的节目
+#[derive(Debug, Clone, Serialize)]
+pub struct EpgGridRow {
+    /// 频道地址，前端用来对应频道
+    pub channel_url: String,
+    pub channel_name: String,
+    pub programmes: Vec<Programme>,
+}
+
+/// 某一天（按前端所在时区）的起止时间：`date` 为 `YYYY-MM-DD`，`utc_offset_minutes` 为当地时间与 UTC 的差
+pub fn day_range(date: &str, utc_offset_minutes: i32) -> Result<(i64, i64), String> {
+    let date = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| format!("无效的日期: {}", date))?;
+    let midnight = date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp();
+    let start = midnight - i64::from(utc_offset_minutes) * 60;
+    Ok((start, start + 24 * 3600))
+}
+
+/// 检查节目表的时间段 `[start, end)`：结束时间必须晚于开始时间，且不超过 [`MAX_GRID_SECS`]
+pub fn check_grid_range(start: i64, end: i64) -> Result<(), String> {
+    if end <= start {
+        return Err("结束时间必须晚于开始时间".to_string());
+    }
+    if end - start > MAX_GRID_SECS {
+        return Err(format!("时间段不能超过 {} 天", MAX_GRID_SECS / (24 * 3600)));
+    }
+    Ok(())
+}
+
+/// 节目单地址的类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpgFeedKind {
+    /// 整体下载的 XMLTV 文件
+    #[default]
+    Xmltv,
+    /// 按频道和日期查询的 DIYP 接口
+    Diyp,
+}
+
+/// 节目单地址的下载状态，保存在 `epg/feeds.json`
+///
+/// 地址中可能带有账号密码（Xtream 的 `xmltv.php`），保存和返回给前端时只包含地址的标识和隐藏密码后的地址。
+/// DIYP 接口的状态不保存，查询时根据缓存的结果统计。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpgFeedStatus {
+    /// 地址的标识（地址的 SHA-256）
+    #[serde(default)]
+    pub id: String,
+    /// 用于显示的地址，密码等参数已隐藏
+    pub url: String,
+    #[serde(default)]
+    pub kind: EpgFeedKind,
+    /// 最近一次成功下载（包括 304）的时间，Unix 时间戳（秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<i64>,
+    #[serde(default)]
+    pub channel_count: usize,
+    #[serde(default)]
+    pub programme_count: usize,
+    /// 最近一次下载失败的原因（保留上次成功下载的节目）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 节目数据文件名（位于 `epg` 目录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    http_cache: Option<HttpValidators>,
+}
+
+impl EpgFeedStatus {
+    fn new(url: &str) -> EpgFeedStatus {
+        EpgFeedStatus {
+            id: feed_id(url),
+            url: redact_url(url),
+            ..Default::default()
+        }
+    }
+
+    fn is_stale(&self, now: i64) -> bool {
+        self.fetched_at.is_none_or(|fetched_at| now - fetched_at >= MAX_AGE_SECS)
+    }
+}
+
+/// 不属于任何订阅源的节目单设置，保存在 `epg/settings.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpgSettings {
+    /// 全局节目单地址（XMLTV 或 DIYP 接口），按优先级排列，所有订阅源都会使用
+    #[serde(default)]
+    pub global_urls: Vec<String>,
+    /// 全局节目单优先于订阅源自带的节目单（默认订阅源自带的优先）
+    #[serde(default)]
+    pub prefer_global: bool,
+}
+
+/// 节目单地址的标识（地址的 SHA-256），用来在不保存原地址的情况下找到对应的状态和节目数据
+fn feed_id(url: &str) -> String {
+    Sha256::digest(url.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 节目单存储：内存中保存已解析的节目，磁盘上保存一份，启动时不用重新下载
+pub struct EpgStore {
+    dir: PathBuf,
+    settings: Mutex<EpgSettings>,
+    /// 地址标识 -> 下载状态
+    feeds: Mutex<HashMap<String, EpgFeedStatus>>,
+    /// 地址标识 -> 节目
+    guides: Mutex<HashMap<String, Arc<Guide>>>,
+    /// 正在下载的地址
+    fetching: Mutex<HashSet<String>>,
+    /// DIYP 接口的查询结果
+    diyp: diyp::DiypCache,
+    aliases: Arc<ChannelAliases>,
+}
+
+/// 正在下载的节目单地址，结束时自动移除
+struct FetchGuard<'a> {
+    fetching: &'a Mutex<HashSet<String>>,
+    url: String,
+}
+
+impl<'a> FetchGuard<'a> {
+    fn acquire(fetching: &'a Mutex<HashSet<String>>, url: &str) -> Option<FetchGuard<'a>> {
+        if !fetching.lock().unwrap().insert(url.to_string()) {
+            return None;
+        }
+        Some(FetchGuard {
+            fetching,
+            url: url.to_string(),
+        })
+    }
+}
+
+impl Drop for FetchGuard<'_> {
+    fn drop(&mut self) {
+        self.fetching.lock().unwrap().remove(&self.url);
+    }
+}
+
+impl EpgStore {
+    pub fn new(data_dir: &Path, aliases: Arc<ChannelAliases>) -> EpgStore {
+        let dir = data_dir.join(EPG_DIR);
+        EpgStore {
+            settings: Mutex::new(load_settings(&dir)),
+            dir,
+            feeds: Mutex::new(HashMap::new()),
+            guides: Mutex::new(HashMap::new()),
+            fetching: Mutex::new(HashSet::new()),
+            diyp: diyp::DiypCache::new(),
+            aliases,
+        }
+    }
+
+    /// 读取保存的下载状态和节目数据；文件损坏时只记录日志，之后会重新下载
+    #[instrument(skip(self), fields(dir = ?self.dir))]
+    fn load(&self) {
+        let feeds_file = self.dir.join(FEEDS_FILE);
+        if !feeds_file.exists() {
+            debug!("没有保存的节目单");
+            return;
+        }
+
+        let feeds: Vec<EpgFeedStatus> = match fs::read_to_string(&feeds_file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        {
+            Ok(feeds) => feeds,
+            Err(e) => {
+                warn!("读取节目单状态失败: {}", e);
+                return;
+            }
+        };
+
+        let mut migrated = false;
+        for mut feed in feeds {
+            // 旧版本保存的是原地址
+            if feed.id.is_empty() {
+                feed.id = feed_id(&feed.url);
+                feed.url = redact_url(&feed.url);
+                migrated = true;
+            }
+            if let Some(file) = &feed.file {
+                match read_guide(&self.dir.join(file)) {
+                    Ok(guide) => {
+                        info!("已加载节目单 {}: {} 个频道，{} 个节目", feed.url, guide.channels.len(), guide.programme_count());
+                        self.guides.lock().unwrap().insert(feed.id.clone(), Arc::new(guide));
+                    }
+                    Err(e) => {
+                        warn!("读取节目单 {} 失败，稍后重新下载: {}", feed.url, e);
+                        feed.fetched_at = None;
+                        feed.http_cache = None;
+                    }
+                }
+            }
+            self.feeds.lock().unwrap().insert(feed.id.clone(), feed);
+        }
+
+        if migrated {
+            if let Err(e) = self.save_feeds() {
+                warn!("保存节目单状态失败: {}", e);
+            }
+        }
+    }
+
+    fn save_feeds(&self) -> Result<(), String> {
+        let feeds: Vec<EpgFeedStatus> = self.feeds.lock().unwrap().values().cloned().collect();
+        let json = serde_json::to_string_pretty(&feeds).map_err(|e| format!("序列化失败: {}", e))?;
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建节目单目录失败: {}", e))?;
+        fs::write(self.dir.join(FEEDS_FILE), json).map_err(|e| format!("写入文件失败: {}", e))
+    }
+
+    pub fn settings(&self) -> EpgSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// 保存全局节目单设置；地址去掉首尾空白和重复项，只接受 http(s) 地址
+    pub fn set_settings(&self, settings: EpgSettings) -> Result<EpgSettings, String> {
+        let mut global_urls: Vec<String> = Vec::new();
+        for url in settings.global_urls.iter().map(|url| url.trim()).filter(|url| !url.is_empty()) {
+            if !is_http_url(url) {
+                return Err(format!("节目单地址必须以 http:// 或 https:// 开头: {}", url));
+            }
+            if !global_urls.iter().any(|u| u == url) {
+                global_urls.push(url.to_string());
+            }
+        }
+        let settings = EpgSettings { global_urls, ..settings };
+
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| format!("序列化失败: {}", e))?;
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建节目单目录失败: {}", e))?;
+        fs::write(self.dir.join(SETTINGS_FILE), json).map_err(|e| format!("写入文件失败: {}", e))?;
+        info!("已保存 {} 个全局节目单地址，全局优先: {}", settings.global_urls.len(), settings.prefer_global);
+
+        *self.settings.lock().unwrap() = settings.clone();
+        Ok(settings)
+    }
+
+    /// 订阅源按优先级排列的节目单地址：订阅源自带的和全局的，顺序由 `prefer_global` 决定
+    pub fn feed_urls(&self, source: &Source) -> Vec<String> {
+        let settings = self.settings.lock().unwrap();
+        let own = source_feed_urls(source);
+        let (first, second) = if settings.prefer_global {
+            (&settings.global_urls, &own)
+        } else {
+            (&own, &settings.global_urls)
+        };
+        let mut urls: Vec<String> = Vec::new();
+        for url in first.iter().chain(second) {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
+
+    /// 多个订阅源和全局设置中的全部节目单地址（去重）
+    pub fn all_feed_urls<'a>(&self, sources: impl IntoIterator<Item = &'a Source>) -> Vec<String> {
+        let mut urls = self.settings.lock().unwrap().global_urls.clone();
+        for url in sources.into_iter().flat_map(|source| self.feed_urls(source)) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    /// 需要下载的 XMLTV 节目单地址
+    pub fn xmltv_feed_urls<'a>(&self, sources: impl IntoIterator<Item = &'a Source>) -> Vec<String> {
+        self.all_feed_urls(sources).into_iter().filter(|url| is_xmltv_url(url)).collect()
+    }
+
+    /// 各节目单地址的状态，顺序与 `urls` 一致；还没有下载过的 XMLTV 节目单只有地址
+    pub fn feed_statuses(&self, urls: &[String]) -> Vec<EpgFeedStatus> {
+        let feeds = self.feeds.lock().unwrap();
+        urls.iter()
+            .map(|url| {
+                if !is_xmltv_url(url) {
+                    let stats = self.diyp.stats(url);
+                    return EpgFeedStatus {
+                        kind: EpgFeedKind::Diyp,
+                        fetched_at: stats.fetched_at,
+                        channel_count: stats.channel_count,
+                        programme_count: stats.programme_count,
+                        error: stats.error,
+                        ..EpgFeedStatus::new(url)
+                    };
+                }
+                feeds.get(&feed_id(url)).cloned().unwrap_or_else(|| EpgFeedStatus::new(url))
+            })
+            .collect()
+    }
+
+    pub fn guide(&self, url: &str) -> Option<Arc<Guide>> {
+        self.guides.lock().unwrap().get(&feed_id(url)).cloned()
+    }
+
+    /// 按地址顺序取第一个能匹配到频道节目的节目单
+    pub fn lookup(&self, urls: &[String], channel: &Channel) -> Option<Arc<Guide>> {
+        urls.iter()
+            .filter_map(|url| self.guide(url))
+            .find(|guide| guide.programmes_for(channel, &self.aliases).is_some())
+    }
+
+    /// XMLTV 节目单中频道在时间段 `[start, end)` 内的节目（未按 tvg-shift 调整）
+    fn xmltv_programmes(&self, url: &str, channel: &Channel, start: i64, end: i64) -> Vec<Programme> {
+        self.guide(url)
+            .and_then(|guide| guide.programmes_for(channel, &self.aliases).map(|p| overlapping(p, start, end).to_vec()))
+            .unwrap_or_default()
+    }
+
+    /// 下载并解析节目单，保存到磁盘；失败时保留原有节目并记录错误
+    ///
+    /// 只有同一地址正在下载时返回 `Err`，下载失败通过 `EpgFeedStatus::error` 返回。
+    #[instrument(skip(self, url), fields(url = %redact_url(url)))]
+    pub async fn fetch(&self, url: &str) -> Result<EpgFeedStatus, String> {
+        let Some(_guard) = FetchGuard::acquire(&self.fetching, url) else {
+            return Err(format!("节目单正在下载: {}", redact_url(url)));
+        };
+
+        let id = feed_id(url);
+        let mut status = self.feeds.lock().unwrap().get(&id).cloned().unwrap_or_else(|| EpgFeedStatus::new(url));
+        // 节目数据丢失时不能发送条件请求
+        let validators = status.http_cache.clone().filter(|_| self.guide(url).is_some());
+
+        match download_guide(url, validators.as_ref()).await {
+            Ok(None) => {
+                info!("节目单没有变化（304 Not Modified）");
+                status.fetched_at = Some(chrono::Utc::now().timestamp());
+                status.error = None;
+            }
+            Ok(Some((guide, validators))) => {
+                let file = status.file.clone().unwrap_or_else(|| format!("{}.json", Uuid::new_v4()));
+                let dir = self.dir.clone();
+                let guide = Arc::new(guide);
+                let saving = guide.clone();
+                let saved = tokio::task::spawn_blocking(move || write_guide(&dir, &file, &saving).map(|_| file))
+                    .await
+                    .map_err(|e| format!("保存节目单失败: {}", e))
+                    .and_then(|result| result);
+                match saved {
+                    Ok(file) => {
+                        status.file = Some(file);
+                        status.http_cache = validators;
+                    }
+                    Err(e) => {
+                        // 内存中的节目仍然可用，下次启动时重新下载
+                        warn!("{}", e);
+                        status.file = None;
+                        status.http_cache = None;
+                    }
+                }
+                status.channel_count = guide.channels.len();
+                status.programme_count = guide.programme_count();
+                status.fetched_at = Some(chrono::Utc::now().timestamp());
+                status.error = None;
+                self.guides.lock().unwrap().insert(id.clone(), guide);
+            }
+            Err(e) => {
+                warn!("节目单下载失败，保留原有节目: {}", e);
+                status.error = Some(e);
+            }
+        }
+
+        self.feeds.lock().unwrap().insert(id, status.clone());
+        if let Err(e) = self.save_feeds() {
+            warn!("保存节目单状态失败: {}", e);
+        }
+        Ok(status)
+    }
+
+    /// 删除不再被任何订阅源使用的节目单
+    fn prune(&self, wanted: &[String]) {
+        let wanted: HashSet<String> = wanted.iter().map(|url| feed_id(url)).collect();
+        let removed: Vec<EpgFeedStatus> = {
+            let mut feeds = self.feeds.lock().unwrap();
+            let ids: Vec<String> = feeds.keys().filter(|id| !wanted.contains(*id)).cloned().collect();
+            ids.iter().filter_map(|id| feeds.remove(id)).collect()
+        };
+        if removed.is_empty() {
+            return;
+        }
+
+        let mut guides = self.guides.lock().unwrap();
+        for feed in &removed {
+            info!("删除不再使用的节目单: {}", feed.url);
+            guides.remove(&feed.id);
+            if let Some(file) = &feed.file {
+                if let Err(e) = fs::remove_file(self.dir.join(file)) {
+                    debug!("删除节目单文件失败: {}", e);
+                }
+            }
+        }
+        drop(guides);
+        if let Err(e) = self.save_feeds() {
+            warn!("保存节目单状态失败: {}", e);
+        }
+    }
+}
+
+/// 读取全局节目单设置，文件不存在或无法解析时为默认设置
+fn load_settings(dir: &Path) -> EpgSettings {
+    let path = dir.join(SETTINGS_FILE);
+    if !path.exists() {
+        return EpgSettings::default();
+    }
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("读取节目单设置失败: {}", e);
+            EpgSettings::default()
+        }
+    }
+}
+
+fn read_guide(path: &Path) -> Result<Guide, String> {
+    let file = fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut guide: Guide = serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+    guide.build_index();
+    Ok(guide)
+}
+
+/// 先写临时文件再替换，写入中途失败不会损坏原有数据
+fn write_guide(dir: &Path, file: &str, guide: &Guide) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建节目单目录失败: {}", e))?;
+    let path = dir.join(file);
+    let tmp = path.with_extension("json.tmp");
+    let writer = fs::File::create(&tmp).map_err(|e| format!("创建文件失败: {}", e))?;
+    serde_json::to_writer(BufWriter::new(writer), guide).map_err(|e| format!("写入节目单失败: {}", e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("保存节目单失败: {}", e))
+}
+
+/// 把异步下载的数据块交给阻塞线程中的 XML 解析器
+struct ChunkReader {
+    receiver: tokio::sync::mpsc::Receiver<Result<Vec<u8>, String>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Some(Err(e)) => return Err(io::Error::other(e)),
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 边下载边解压（gzip 等）、边解析 XMLTV；服务器返回 304 时返回 None
+async fn download_guide(url: &str, validators: Option<&HttpValidators>) -> Result<Option<(Guide, Option<HttpValidators>)>, String> {
+    let client = SourceHttpSettings::default().client()?;
+    let mut request = client.get(url);
+    if let Some(validators) = validators {
+        request = validators.apply(request);
+    }
+
+    let mut response = request.send().await.map_err(|e| format!("下载失败: {}", e.without_url()))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("下载失败: HTTP {}", response.status()));
+    }
+    let validators = HttpValidators::from_headers(response.headers());
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(CHUNK_BUFFER);
+    let parser = tokio::task::spawn_blocking(move || {
+        xmltv::parse(BufReader::new(ChunkReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }))
+    });
+
+    let downloaded = async {
+        let mut decompressor = Decompressor::new(url);
+        let mut bytes_read = 0u64;
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("读取内容失败: {}", e.without_url()))? {
+            bytes_read += chunk.len() as u64;
+            let data = decompressor.push(&chunk)?;
+            // 解析出错时接收端已关闭，不再继续下载
+            if !data.is_empty() && sender.send(Ok(data)).await.is_err() {
+                return Ok(bytes_read);
+            }
+        }
+        let data = decompressor.finish()?;
+        let _ = sender.send(Ok(data)).await;
+        Ok::<u64, String>(bytes_read)
+    }
+    .await;
+    if let Err(e) = &downloaded {
+        let _ = sender.send(Err(e.clone())).await;
+    }
+    drop(sender);
+
+    let parsed = parser.await.map_err(|e| format!("解析节目单失败: {}", e))?;
+    let bytes_read = downloaded?;
+    let guide = parsed?;
+    info!("节目单下载完成，大小: {} 字节", bytes_read);
+    Ok(Some((guide, validators)))
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// 需要整体下载的 XMLTV 节目单地址；DIYP 接口按频道查询，不需要下载
+fn is_xmltv_url(url: &str) -> bool {
+    !diyp::is_diyp_url(url)
+}
+
+/// 订阅源自带的节目单地址（XMLTV 和 DIYP 接口），按订阅源中的顺序
+fn source_feed_urls(source: &Source) -> Vec<String> {
+    source.meta.epg_urls.iter().filter(|url| is_http_url(url)).cloned().collect()
+}
+
+/// 订阅源中匹配到节目单的频道数
+pub fn matched_channels(store: &EpgStore, source: &Source) -> usize {
+    let urls = store.feed_urls(source);
+    source.channels.iter().filter(|channel| store.lookup(&urls, channel).is_some()).count()
+}
+
+/// 频道在时间段 `[start, end)` 内的节目，时间已按 tvg-shift 调整
+///
+/// 按节目单地址的优先级查询，使用第一个在这个时间段内有节目的节目单（不同节目单的节目不混合）：
+/// XMLTV 从内存中读取，DIYP 接口按天查询并缓存。
+pub async fn schedule(store: &EpgStore, urls: &[String], channel: &Channel, start: i64, end: i64) -> Vec<Programme> {
+    let shift = shift_secs(channel);
+    let (start, end) = (start - shift, end - shift);
+    for url in urls {
+        let programmes = if diyp::is_diyp_url(url) {
+            let name = store.aliases.canonical(channel.tvg_name.as_deref().unwrap_or(&channel.name));
+            store.diyp.programmes(url, &name, start, end).await
+        } else {
+            store.xmltv_programmes(url, channel, start, end)
+        };
+        if !programmes.is_empty() {
+            return programmes.iter().map(|p| p.shifted(shift)).collect();
+        }
+    }
+    Vec::new()
+}
+
+/// 并发查询多个频道的节目（DIYP 接口的请求数另有限制），结果与 `channels` 的顺序一致
+pub async fn schedule_many(app: &AppHandle, urls: &[String], channels: Vec<Channel>, start: i64, end: i64) -> Vec<(Channel, Vec<Programme>)> {
+    let urls = Arc::new(urls.to_vec());
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, channel) in channels.into_iter().enumerate() {
+        let app = app.clone();
+        let urls = urls.clone();
+        tasks.spawn(async move {
+            let state = app.state::<AppState>();
+            let programmes = schedule(&state.epg, &urls, &channel, start, end).await;
+            (index, channel, programmes)
+        });
+    }
+
+    let mut results = Vec::with_capacity(tasks.len());
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => warn!("查询节目失败: {}", e),
+        }
+    }
+    results.sort_by_key(|(index, ..)| *index);
+    results.into_iter().map(|(_, channel, programmes)| (channel, programmes)).collect()
+}
+
+/// 多个频道在 `at` 时刻的当前节目和下一个节目
+pub async fn now_next_many(app: &AppHandle, urls: &[String], channels: Vec<Channel>, at: i64) -> Vec<(Channel, NowNext)> {
+    schedule_many(app, urls, channels, at, at + NEXT_LOOKAHEAD_SECS)
+        .await
+        .into_iter()
+        .map(|(channel, programmes)| {
+            let now_next = NowNext::at(&programmes, at);
+            (channel, now_next)
+        })
+        .collect()
+}
+
+/// 依次下载节目单，每个完成后发送 `epg-updated` 事件
+pub async fn fetch_all(app: &AppHandle, urls: &[String]) -> Vec<EpgFeedStatus> {
+    let state = app.state::<AppState>();
+    let mut results = Vec::new();
+    for url in urls {
+        match state.epg.fetch(url).await {
+            Ok(status) => {
+                if let Err(e) = app.emit(EPG_EVENT, &status) {
+                    warn!("发送节目单事件失败: {}", e);
+                }
+                results.push(status);
+            }
+            Err(e) => debug!("跳过节目单: {}", e),
+        }
+    }
+    results
+}
+
+/// 下载订阅源使用但还没有下载或已经过期的节目单，删除不再使用的节目单
+pub async fn sync(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let urls = state.epg.xmltv_feed_urls(state.sources.lock().unwrap().iter());
+    state.epg.prune(&urls);
+
+    let now = chrono::Utc::now().timestamp();
+    let due: Vec<String> = {
+        let feeds = state.epg.feeds.lock().unwrap();
+        urls.into_iter()
+            .filter(|url| feeds.get(&feed_id(url)).is_none_or(|feed| feed.is_stale(now)))
+            .collect()
+    };
+    if due.is_empty() {
+        return;
+    }
+    info!("{} 个节目单需要更新", due.len());
+    fetch_all(app, &due).await;
+}
+
+/// 订阅源变化后在后台同步节目单
+pub fn spawn_sync(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { sync(&app).await });
+}
+
+/// 启动时读取保存的节目单，之后定期检查是否需要更新
+pub async fn run(app: AppHandle) {
+    let loader = app.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || loader.state::<AppState>().epg.load()).await {
+        error!("读取节目单失败: {}", e);
+    }
+
+    let mut ticker = tokio::time::interval(SYNC_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        sync(&app).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::Router;
+
+    const XMLTV: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+  <channel id="cctv1"><display-name>CCTV1</display-name></channel>
+  <programme channel="cctv1" start="20261018200000 +0800" stop="20261018210000 +0800"><title>新闻联播</title></programme>
+</tv>"#;
+
+    async fn start_server() -> String {
+        let app = Router::new().route("/xmltv.php", get(|| async { XMLTV }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/xmltv.php?username=user&password=secret", addr)
+    }
+
+    fn store(dir: &Path) -> EpgStore {
+        EpgStore::new(dir, Arc::new(ChannelAliases::load(dir)))
+    }
+
+    #[tokio::test]
+    async fn feed_urls_are_stored_and_returned_redacted() {
+        let url = start_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let epg = store(dir.path());
+
+        let status = epg.fetch(&url).await.unwrap();
+        assert_eq!(status.error, None);
+        assert_eq!(status.programme_count, 1);
+        assert!(!status.url.contains("secret"));
+        assert!(status.url.contains("username=user"));
+        assert!(!serde_json::to_string(&status).unwrap().contains("secret"));
+
+        let statuses = epg.feed_statuses(std::slice::from_ref(&url));
+        assert_eq!(statuses[0].id, status.id);
+        assert!(!statuses[0].url.contains("secret"));
+
+        let saved = fs::read_to_string(dir.path().join(EPG_DIR).join(FEEDS_FILE)).unwrap();
+        assert!(!saved.contains("secret"));
+
+        // 重新启动后通过原地址找到保存的节目
+        let reloaded = store(dir.path());
+        reloaded.load();
+        assert_eq!(reloaded.guide(&url).unwrap().programme_count(), 1);
+        assert_eq!(reloaded.feed_statuses(&[url])[0].fetched_at, status.fetched_at);
+    }
+
+    #[test]
+    fn migrates_feeds_saved_with_plain_urls() {
+        let url = "http://example.com/xmltv.php?username=user&password=secret";
+        let dir = tempfile::tempdir().unwrap();
+        let epg_dir = dir.path().join(EPG_DIR);
+        fs::create_dir_all(&epg_dir).unwrap();
+        let legacy = serde_json::json!([{ "url": url, "kind": "xmltv", "fetched_at": 1, "channel_count": 2, "programme_count": 3 }]);
+        fs::write(epg_dir.join(FEEDS_FILE), legacy.to_string()).unwrap();
+
+        let epg = store(dir.path());
+        epg.load();
+        let status = &epg.feed_statuses(&[url.to_string()])[0];
+        assert_eq!(status.fetched_at, Some(1));
+        assert_eq!(status.programme_count, 3);
+        assert!(!status.url.contains("secret"));
+        assert!(!fs::read_to_string(epg_dir.join(FEEDS_FILE)).unwrap().contains("secret"));
+    }
+
+    /// 自带节目单只有前一天的节目，全局节目单两天都有
+    const OWN_XMLTV: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+  <channel id="cctv1"><display-name>CCTV1</display-name></channel>
+  <programme channel="cctv1" start="20261017200000 +0800" stop="20261017210000 +0800"><title>自带</title></programme>
+</tv>"#;
+
+    const GLOBAL_XMLTV: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+  <channel id="CCTV-1"><display-name>CCTV-1 综合</display-name></channel>
+  <programme channel="CCTV-1" start="20261017200000 +0800" stop="20261017210000 +0800"><title>全局</title></programme>
+  <programme channel="CCTV-1" start="20261018200000 +0800" stop="20261018210000 +0800"><title>新闻联播</title></programme>
+</tv>"#;
+
+    /// 2026-10-18 20:00 (+0800) 开始的一小时
+    const EVENING: (i64, i64) = (1_792_324_800, 1_792_328_400);
+
+    async fn start_priority_server() -> (String, String) {
+        let app = Router::new()
+            .route("/own.xml", get(|| async { OWN_XMLTV }))
+            .route("/global.xml", get(|| async { GLOBAL_XMLTV }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/own.xml", addr), format!("http://{}/global.xml", addr))
+    }
+
+    fn source_with_epg(epg_urls: &[&str]) -> Source {
+        serde_json::from_value(serde_json::json!({
+            "id": "source-1",
+            "name": "测试",
+            "kind": { "type": "remote", "url": "http://a.com/live.m3u" },
+            "channels": [],
+            "meta": { "epg_urls": epg_urls },
+        }))
+        .unwrap()
+    }
+
+    fn titles(programmes: &[Programme]) -> Vec<&str> {
+        programmes.iter().map(|p| p.title.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn schedule_falls_back_to_next_feed_with_programmes() {
+        let (own, global) = start_priority_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let epg = store(dir.path());
+        epg.fetch(&own).await.unwrap();
+        epg.fetch(&global).await.unwrap();
+
+        let channel = Channel { name: "CCTV1".to_string(), ..Default::default() };
+        let (start, end) = EVENING;
+        let urls = [own.clone(), global.clone()];
+        // 自带节目单在这个时间段没有节目，使用下一个节目单
+        assert_eq!(titles(&schedule(&epg, &urls, &channel, start, end).await), ["新闻联播"]);
+        // 两个节目单都有节目时只使用优先的一个，不混合
+        let yesterday = (start - 86400, end - 86400);
+        assert_eq!(titles(&schedule(&epg, &urls, &channel, yesterday.0, yesterday.1).await), ["自带"]);
+        let urls = [global, own];
+        assert_eq!(titles(&schedule(&epg, &urls, &channel, yesterday.0, yesterday.1).await), ["全局"]);
+        assert!(schedule(&epg, &urls, &channel, start + 86400, end + 86400).await.is_empty());
+    }
+
+    #[test]
+    fn prefer_global_changes_feed_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let epg = store(dir.path());
+        let (own, global) = ("http://a.com/own.xml", "http://b.com/global.xml");
+        let source = source_with_epg(&[own, "file:///tmp/e.xml", global]);
+        let settings = EpgSettings { global_urls: vec![global.to_string(), "http://c.com/e.xml".to_string()], prefer_global: false };
+        epg.set_settings(settings.clone()).unwrap();
+        assert_eq!(epg.feed_urls(&source), [own, global, "http://c.com/e.xml"]);
+
+        epg.set_settings(EpgSettings { prefer_global: true, ..settings }).unwrap();
+        assert_eq!(epg.feed_urls(&source), [global, "http://c.com/e.xml", own]);
+    }
+
+    fn programme(start: i64, stop: i64) -> Programme {
+        Programme { start, stop, title: format!("{}-{}", start, stop), ..Default::default() }
+    }
+
+    fn spans(programmes: &[Programme]) -> Vec<(i64, i64)> {
+        programmes.iter().map(|p| (p.start, p.stop)).collect()
+    }
+
+    #[test]
+    fn overlapping_includes_programme_in_progress() {
+        let programmes = [programme(0, 100), programme(100, 200), programme(200, 300), programme(300, 400)];
+        assert_eq!(spans(overlapping(&programmes, 150, 250)), [(100, 200), (200, 300)]);
+        // 结束时间不包含在内，刚好结束的节目也不算
+        assert_eq!(spans(overlapping(&programmes, 100, 200)), [(100, 200)]);
+        assert_eq!(spans(overlapping(&programmes, 0, 1)), [(0, 100)]);
+        assert!(overlapping(&programmes, 400, 500).is_empty());
+        assert!(overlapping(&programmes, -100, 0).is_empty());
+        assert!(overlapping(&[], 0, 100).is_empty());
+    }
+
+    #[test]
+    fn now_next_with_gaps() {
+        let programmes = [programme(0, 100), programme(200, 300)];
+        let now_next = NowNext::at(&programmes, 50);
+        assert_eq!(now_next.now, Some(programme(0, 100)));
+        assert_eq!(now_next.next, Some(programme(200, 300)));
+
+        // 两个节目之间没有节目
+        let now_next = NowNext::at(&programmes, 150);
+        assert_eq!(now_next.now, None);
+        assert_eq!(now_next.next, Some(programme(200, 300)));
+
+        let now_next = NowNext::at(&programmes, 300);
+        assert!(now_next.is_empty());
+
+        let now_next = NowNext::at(&programmes, -10);
+        assert_eq!(now_next.now, None);
+        assert_eq!(now_next.next, Some(programme(0, 100)));
+    }
+
+    #[test]
+    fn day_range_uses_utc_offset() {
+        // 2026-10-18 00:00 UTC
+        let midnight = 1_792_281_600;
+        assert_eq!(day_range("2026-10-18", 0), Ok((midnight, midnight + 86400)));
+        assert_eq!(day_range(" 2026-10-18 ", 480), Ok((midnight - 8 * 3600, midnight + 16 * 3600)));
+        assert_eq!(day_range("2026-10-18", -300), Ok((midnight + 5 * 3600, midnight + 29 * 3600)));
+        assert_eq!(day_range("2026-02-30", 0), Err("无效的日期: 2026-02-30".to_string()));
+        assert!(day_range("20261018", 0).is_err());
+    }
+
+    #[test]
+    fn grid_range_is_limited() {
+        assert!(check_grid_range(0, 86400).is_ok());
+        assert!(check_grid_range(0, MAX_GRID_SECS).is_ok());
+        assert_eq!(check_grid_range(0, MAX_GRID_SECS + 1), Err("时间段不能超过 3 天".to_string()));
+        assert!(check_grid_range(100, 100).is_err());
+        assert!(check_grid_range(100, 0).is_err());
+    }
+
+    #[test]
+    fn prunes_unused_feeds() {
+        let dir = tempfile::tempdir().unwrap();
+        let epg = store(dir.path());
+        let (kept, removed) = ("http://a.com/e.xml".to_string(), "http://b.com/e.xml".to_string());
+        for url in [&kept, &removed] {
+            epg.feeds.lock().unwrap().insert(feed_id(url), EpgFeedStatus::new(url));
+        }
+        epg.prune(std::slice::from_ref(&kept));
+        let feeds = epg.feeds.lock().unwrap();
+        assert!(feeds.contains_key(&feed_id(&kept)));
+        assert!(!feeds.contains_key(&feed_id(&removed)));
+    }
+}
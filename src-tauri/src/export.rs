@@ -0,0 +1,357 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::playlist::Playlist;
+use crate::{Channel, PlaylistMeta};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// 扩展 M3U，保留全部属性
+    M3u,
+    /// DIYP / TXT 格式
+    Txt,
+    /// 与 sources.json 相同结构的 JSON
+    Json,
+}
+
+/// 导出时的频道筛选条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportFilter {
+    /// 只导出这些分组（为空时导出全部），未分组的频道用空字符串表示
+    pub groups: Vec<String>,
+    /// 频道名包含的关键字（不区分大小写）
+    pub search: Option<String>,
+    /// 导出前合并重复频道
+    pub merge_duplicates: bool,
+}
+
+impl ExportFilter {
+    fn matches(&self, channel: &Channel) -> bool {
+        if !self.groups.is_empty() {
+            let group = channel.group.as_deref().unwrap_or("");
+            if !self.groups.iter().any(|g| g == group) {
+                return false;
+            }
+        }
+        match self.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(search) => channel.name.to_lowercase().contains(&search.to_lowercase()),
+            None => true,
+        }
+    }
+
+    /// 按条件筛选频道，需要时合并重复频道
+    pub fn apply(&self, meta: &PlaylistMeta, channels: &[Channel]) -> Playlist {
+        let mut playlist = Playlist {
+            meta: meta.clone(),
+            channels: channels.iter().filter(|c| self.matches(c)).cloned().collect(),
+            encoding: None,
+            report: Default::default(),
+        };
+        if self.merge_duplicates {
+            playlist.merge_duplicates();
+        }
+        playlist
+    }
+}
+
+/// 按指定格式生成导出内容
+pub fn export_playlist(name: &str, playlist: &Playlist, format: ExportFormat) -> Result<String, String> {
+    let content = match format {
+        ExportFormat::M3u => to_m3u(playlist),
+        ExportFormat::Txt => to_txt(&playlist.channels),
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "name": name,
+            "meta": playlist.meta,
+            "channels": playlist.channels,
+        }))
+        .map_err(|e| format!("序列化失败: {}", e))?,
+    };
+
+    info!("导出 {} 个频道，格式: {:?}，大小: {} 字节", playlist.channels.len(), format, content.len());
+    Ok(content)
+}
+
+/// 属性值中的双引号无法转义，替换为单引号
+fn push_attribute(line: &mut String, key: &str, value: &str) {
+    line.push(' ');
+    line.push_str(key);
+    line.push_str("=\"");
+    line.push_str(&value.replace('"', "'"));
+    line.push('"');
+}
+
+/// 按键排序输出，保证多次导出结果一致；无法作为属性名的键会被丢弃
+fn sorted(map: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = map
+        .iter()
+        .filter(|(key, _)| !key.is_empty() && !key.contains(|c: char| c.is_whitespace() || c == '"' || c == '=' || c == ','))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// 生成扩展 M3U：头部写入 EPG、回看等列表级属性，频道写入全部已知属性和原始选项
+///
+/// 合并后的备用地址以相同的 `#EXTINF` 重复输出，重新导入时开启合并即可还原。
+fn to_m3u(playlist: &Playlist) -> String {
+    let meta = &playlist.meta;
+    let mut output = String::from("#EXTM3U");
+
+    if !meta.epg_urls.is_empty() {
+        push_attribute(&mut output, "x-tvg-url", &meta.epg_urls.join(","));
+    }
+    if let Some(catchup) = &meta.catchup {
+        push_attribute(&mut output, "catchup", catchup);
+    }
+    if let Some(source) = &meta.catchup_source {
+        push_attribute(&mut output, "catchup-source", source);
+    }
+    if let Some(days) = meta.catchup_days {
+        push_attribute(&mut output, "catchup-days", &days.to_string());
+    }
+    if let Some(shift) = meta.tvg_shift {
+        push_attribute(&mut output, "tvg-shift", &shift.to_string());
+    }
+    if let Some(user_agent) = &meta.user_agent {
+        push_attribute(&mut output, "user-agent", user_agent);
+    }
+    for (key, value) in sorted(&meta.attributes) {
+        push_attribute(&mut output, key, value);
+    }
+    output.push('\n');
+
+    for channel in &playlist.channels {
+        let extinf = extinf_line(channel);
+        let options = option_lines(channel, meta);
+
+        for url in std::iter::once(&channel.url).chain(&channel.backup_urls) {
+            output.push_str(&extinf);
+            output.push('\n');
+            for option in &options {
+                output.push_str(option);
+                output.push('\n');
+            }
+            output.push_str(url);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn extinf_line(channel: &Channel) -> String {
+    let mut line = String::from("#EXTINF:-1");
+
+    let fields = [
+        ("tvg-id", channel.tvg_id.clone()),
+        ("tvg-name", channel.tvg_name.clone()),
+        ("tvg-logo", channel.logo.clone()),
+        ("tvg-chno", channel.tvg_chno.map(|n| n.to_string())),
+        ("tvg-shift", channel.tvg_shift.map(|n| n.to_string())),
+        ("group-title", channel.group.clone()),
+        ("catchup", channel.catchup.clone()),
+        ("catchup-source", channel.catchup_source.clone()),
+        ("catchup-days", channel.catchup_days.map(|n| n.to_string())),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            push_attribute(&mut line, key, &value);
+        }
+    }
+    for (key, value) in sorted(&channel.attributes) {
+        push_attribute(&mut line, key, value);
+    }
+
+    line.push(',');
+    line.push_str(&channel.name);
+    line
+}
+
+/// `#EXTVLCOPT` / `#KODIPROP` 行；没有原始选项但有请求头时（例如来自 TVBox 配置）按 VLC 选项写出，
+/// VLC 没有对应选项的请求头写入 `inputstream.adaptive.stream_headers`
+fn option_lines(channel: &Channel, meta: &PlaylistMeta) -> Vec<String> {
+    let mut lines: Vec<String> = sorted(&channel.vlc_options)
+        .into_iter()
+        .map(|(key, value)| format!("#EXTVLCOPT:{}={}", key, value))
+        .collect();
+
+    if channel.vlc_options.is_empty() && channel.kodi_props.is_empty() {
+        let http = &channel.http;
+        // 继承自列表头部的 UA 已经写在 #EXTM3U 中
+        if let Some(user_agent) = http.user_agent.as_ref().filter(|ua| meta.user_agent.as_ref() != Some(*ua)) {
+            lines.push(format!("#EXTVLCOPT:http-user-agent={}", user_agent));
+        }
+        if let Some(referrer) = &http.referrer {
+            lines.push(format!("#EXTVLCOPT:http-referrer={}", referrer));
+        }
+        let mut stream_headers = Vec::new();
+        for (name, value) in sorted(&http.headers) {
+            match name.to_ascii_lowercase().as_str() {
+                "origin" => lines.push(format!("#EXTVLCOPT:http-origin={}", value)),
+                "cookie" => lines.push(format!("#EXTVLCOPT:http-cookie={}", value)),
+                _ => stream_headers.push(format!("{}={}", name, urlencoding::encode(value))),
+            }
+        }
+        if !stream_headers.is_empty() {
+            lines.push(format!("#KODIPROP:inputstream.adaptive.stream_headers={}", stream_headers.join("&")));
+        }
+    }
+
+    lines.extend(
+        sorted(&channel.kodi_props)
+            .into_iter()
+            .map(|(key, value)| format!("#KODIPROP:{}={}", key, value)),
+    );
+    lines
+}
+
+/// 生成 DIYP / TXT：按分组首次出现的顺序输出，备用地址用 `#` 连接
+fn to_txt(channels: &[Channel]) -> String {
+    let mut groups: Vec<(&str, Vec<&Channel>)> = Vec::new();
+    for channel in channels {
+        let group = channel.group.as_deref().unwrap_or("未分组");
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, list)) => list.push(channel),
+            None => groups.push((group, vec![channel])),
+        }
+    }
+
+    let mut output = String::new();
+    for (group, list) in groups {
+        output.push_str(&format!("{},#genre#\n", group.replace(',', " ")));
+        for channel in list {
+            let urls: Vec<&str> = std::iter::once(channel.url.as_str())
+                .chain(channel.backup_urls.iter().map(String::as_str))
+                .collect();
+            output.push_str(&format!("{},{}\n", channel.name.replace(',', " "), urls.join("#")));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist::parse_playlist_content;
+
+    fn channel(name: &str, group: Option<&str>, url: &str) -> Channel {
+        Channel {
+            name: name.to_string(),
+            url: url.to_string(),
+            group: group.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn m3u_round_trip_keeps_attributes_options_and_backups() {
+        let content = "#EXTM3U x-tvg-url=\"http://e/1.xml,http://e/2.xml\" catchup=\"append\" catchup-source=\"?s={utc}\" x-custom=\"1\"\n\
+                       #EXTINF:-1 tvg-id=\"cctv1\" tvg-name=\"CCTV-1\" tvg-logo=\"http://logo/1.png\" tvg-chno=\"1\" tvg-shift=\"-1.5\" group-title=\"央视\" catchup-days=\"7\" CUSTOM=\"v\",CCTV1 综合\n\
+                       #EXTVLCOPT:http-user-agent=VLC\n#EXTVLCOPT:http-referrer=http://r/\nhttp://a/1\n\
+                       #EXTINF:-1 tvg-id=\"cctv1\" tvg-name=\"CCTV-1\" tvg-logo=\"http://logo/1.png\" tvg-chno=\"1\" tvg-shift=\"-1.5\" group-title=\"央视\" catchup-days=\"7\" CUSTOM=\"v\",CCTV1 综合\n\
+                       #EXTVLCOPT:http-user-agent=VLC\n#EXTVLCOPT:http-referrer=http://r/\nhttp://b/1\n\
+                       #EXTINF:-1 group-title=\"卫视\",湖南卫视\n#KODIPROP:inputstream.adaptive.license_type=clearkey\nhttp://a/hunan\n";
+        let mut original = parse_playlist_content(content, "list.m3u").unwrap();
+        original.merge_duplicates();
+        assert_eq!(original.channels[0].backup_urls, ["http://b/1"]);
+
+        let exported = export_playlist("测试", &original, ExportFormat::M3u).unwrap();
+        let mut imported = parse_playlist_content(&exported, "export.m3u").unwrap();
+        imported.merge_duplicates();
+
+        assert_eq!(imported.meta.epg_urls, original.meta.epg_urls);
+        assert_eq!(imported.meta.catchup_source, original.meta.catchup_source);
+        assert_eq!(imported.meta.attributes, original.meta.attributes);
+        assert_eq!(imported.channels.len(), 2);
+        for (before, after) in original.channels.iter().zip(&imported.channels) {
+            assert_eq!(serde_json::to_value(before).unwrap(), serde_json::to_value(after).unwrap());
+        }
+        assert_eq!(imported.channels[0].tvg_shift, Some(-1.5));
+        assert_eq!(imported.channels[0].http.user_agent.as_deref(), Some("VLC"));
+        assert_eq!(imported.channels[1].kodi_props.get("inputstream.adaptive.license_type").map(String::as_str), Some("clearkey"));
+    }
+
+    #[test]
+    fn m3u_export_keeps_request_headers_without_raw_options() {
+        let mut ch = channel("A", None, "http://a/1");
+        ch.http.user_agent = Some("okhttp".to_string());
+        ch.http.referrer = Some("http://r/".to_string());
+        ch.http.headers = map(&[("Origin", "http://o"), ("Cookie", "a=b"), ("X-Token", "t=1&2"), ("Authorization", "Bearer x")]);
+        let playlist = Playlist::from_channels(vec![ch.clone()]);
+
+        let exported = export_playlist("测试", &playlist, ExportFormat::M3u).unwrap();
+        let imported = parse_playlist_content(&exported, "export.m3u").unwrap();
+        let http = &imported.channels[0].http;
+        assert_eq!(http.user_agent, ch.http.user_agent);
+        assert_eq!(http.referrer, ch.http.referrer);
+        assert_eq!(http.headers, ch.http.headers);
+    }
+
+    #[test]
+    fn txt_round_trip_keeps_groups_and_backups() {
+        let mut first = channel("CCTV1", Some("央视"), "http://a/1");
+        first.backup_urls = vec!["http://b/1".to_string()];
+        let channels = vec![
+            first,
+            channel("湖南卫视", Some("卫视"), "http://a/hunan"),
+            channel("CCTV2", Some("央视"), "http://a/2"),
+            channel("其他, 频道", None, "http://a/3"),
+        ];
+        let exported = to_txt(&channels);
+        assert!(exported.starts_with("央视,#genre#\nCCTV1,http://a/1#http://b/1\nCCTV2,http://a/2\n卫视,#genre#\n"));
+
+        let imported = parse_playlist_content(&exported, "").unwrap();
+        let summary: Vec<(&str, Option<&str>, &str, &[String])> = imported
+            .channels
+            .iter()
+            .map(|c| (c.name.as_str(), c.group.as_deref(), c.url.as_str(), c.backup_urls.as_slice()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("CCTV1", Some("央视"), "http://a/1", &["http://b/1".to_string()][..]),
+                ("CCTV2", Some("央视"), "http://a/2", &[][..]),
+                ("湖南卫视", Some("卫视"), "http://a/hunan", &[][..]),
+                ("其他  频道", Some("未分组"), "http://a/3", &[][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_by_group_search_and_merge() {
+        let channels = vec![
+            channel("CCTV1", Some("央视"), "http://a/1"),
+            channel("CCTV1", Some("央视"), "http://b/1"),
+            channel("cctv2", Some("央视"), "http://a/2"),
+            channel("湖南卫视", Some("卫视"), "http://a/hunan"),
+            channel("未分组频道", None, "http://a/x"),
+        ];
+        let meta = PlaylistMeta::default();
+        let names = |filter: &ExportFilter| -> Vec<String> { filter.apply(&meta, &channels).channels.into_iter().map(|c| c.name).collect() };
+
+        let filter = ExportFilter {
+            groups: vec!["央视".to_string(), String::new()],
+            ..Default::default()
+        };
+        assert_eq!(names(&filter), ["CCTV1", "CCTV1", "cctv2", "未分组频道"]);
+
+        let filter = ExportFilter {
+            search: Some(" CCTV ".to_string()),
+            merge_duplicates: true,
+            ..Default::default()
+        };
+        let playlist = filter.apply(&meta, &channels);
+        assert_eq!(playlist.channels.len(), 2);
+        assert_eq!(playlist.channels[0].backup_urls, ["http://b/1"]);
+
+        assert_eq!(names(&ExportFilter::default()).len(), 5);
+    }
+}
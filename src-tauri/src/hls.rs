@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+/// 路径中不能作为频道名的常见文件名
+const GENERIC_NAMES: &[&str] = &[
+    "index", "master", "playlist", "live", "stream", "chunklist", "mono", "main", "video", "prog_index", "manifest", "hls",
+];
+
+/// 只会是媒体分片的扩展名（MPEG-TS、fMP4）；`.mp4` / `.mp3` 等也常见于普通的点播和音乐列表，不算在内
+const SEGMENT_EXTENSIONS: &[&str] = &["ts", "m4s"];
+
+/// 只在媒体播放列表中出现的指令
+const MEDIA_PLAYLIST_TAGS: &[&str] = &[
+    "#EXT-X-TARGETDURATION",
+    "#EXT-X-MEDIA-SEQUENCE",
+    "#EXT-X-MAP",
+    "#EXT-X-PLAYLIST-TYPE",
+    "#EXT-X-ENDLIST",
+    "#EXT-X-DISCONTINUITY-SEQUENCE",
+];
+
+/// HLS 主播放列表中的一个清晰度（`#EXT-X-STREAM-INF`）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HlsVariant {
+    pub url: String,
+    /// 峰值码率（bit/s）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bandwidth: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_bandwidth: Option<u64>,
+    /// 例如 `1920x1080`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    /// 例如 `avc1.640028,mp4a.40.2`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codecs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_rate: Option<f32>,
+}
+
+impl HlsVariant {
+    /// 解析 `#EXT-X-STREAM-INF:` 后面的属性，地址在下一行
+    pub fn from_stream_inf(attributes: &str) -> HlsVariant {
+        let mut variant = HlsVariant::default();
+        for (key, value) in parse_attribute_list(attributes) {
+            match key.as_str() {
+                "BANDWIDTH" => variant.bandwidth = value.parse().ok(),
+                "AVERAGE-BANDWIDTH" => variant.average_bandwidth = value.parse().ok(),
+                "RESOLUTION" => variant.resolution = Some(value),
+                "CODECS" => variant.codecs = Some(value),
+                "FRAME-RATE" => variant.frame_rate = value.parse().ok(),
+                _ => {}
+            }
+        }
+        variant
+    }
+}
+
+/// 解析 HLS 属性列表 `KEY=value,KEY="quoted,value"`
+pub fn parse_attribute_list(input: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let Some(eq) = rest.find('=') else {
+            break;
+        };
+        let key = rest[..eq].trim().to_ascii_uppercase();
+        rest = &rest[eq + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = &quoted[..end];
+            rest = quoted.get(end + 1..).unwrap_or("");
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        attributes.push((key, value.trim().to_string()));
+
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+    }
+    attributes
+}
+
+/// 是否为只在媒体播放列表中出现的指令
+pub fn is_media_playlist_tag(line: &str) -> bool {
+    MEDIA_PLAYLIST_TAGS.iter().any(|tag| line.starts_with(tag))
+}
+
+/// 地址是否像媒体分片（`.ts` / `.m4s` 等）
+pub fn is_segment_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| SEGMENT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// 相对地址按播放列表地址解析，无法解析时原样返回
+pub fn resolve_url(base: &str, url: &str) -> String {
+    reqwest::Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map(String::from)
+        .unwrap_or_else(|_| url.to_string())
+}
+
+/// 重写播放列表中的地址：地址行，以及指令中的 `URI="..."` 属性（`#EXT-X-KEY`、`#EXT-X-MAP`、`#EXT-X-MEDIA` 等）
+///
+/// 相对地址（包括 `/seg.ts`、`../seg.ts`）先按播放列表地址解析为绝对地址，再交给 `map` 转换。
+pub fn rewrite_playlist(content: &str, base: &str, mut map: impl FnMut(String) -> String) -> String {
+    content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                line.to_string()
+            } else if trimmed.starts_with('#') {
+                rewrite_uri_attributes(line, base, &mut map)
+            } else {
+                map(resolve_url(base, trimmed))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn rewrite_uri_attributes(line: &str, base: &str, map: &mut impl FnMut(String) -> String) -> String {
+    const MARKER: &str = "URI=\"";
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(MARKER) {
+        let value_start = start + MARKER.len();
+        // 只处理完整的属性名（前面是 `:` 或 `,`），不处理 `XURI="` 之类
+        let is_attribute = start == 0 || matches!(rest.as_bytes()[start - 1], b':' | b',' | b' ');
+        let Some(len) = rest[value_start..].find('"') else {
+            break;
+        };
+        output.push_str(&rest[..value_start]);
+        let value = &rest[value_start..value_start + len];
+        if is_attribute {
+            output.push_str(&map(resolve_url(base, value)));
+        } else {
+            output.push_str(value);
+        }
+        rest = &rest[value_start + len..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 从流地址推断频道名：取路径中最后一个有意义的部分，`index.m3u8` 之类的通用文件名向上取目录名，都没有时使用主机名
+pub fn channel_name_from_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "直播视频".to_string();
+    };
+
+    let segments: Vec<String> = parsed
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|s| !s.is_empty())
+                .map(|s| urlencoding::decode(s).map(|d| d.into_owned()).unwrap_or_else(|_| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for segment in segments.iter().rev() {
+        let stem = segment.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(segment);
+        if !stem.is_empty() && !GENERIC_NAMES.contains(&stem.to_ascii_lowercase().as_str()) {
+            return stem.to_string();
+        }
+    }
+
+    parsed.host_str().map(str::to_string).unwrap_or_else(|| "直播视频".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "http://cdn.example.com/live/ch1/index.m3u8?token=1";
+
+    fn proxy(url: String) -> String {
+        format!("proxy({})", url)
+    }
+
+    fn pairs(input: &str) -> Vec<(String, String)> {
+        parse_attribute_list(input)
+    }
+
+    #[test]
+    fn attribute_list_handles_quoted_commas_and_spaces() {
+        assert_eq!(
+            pairs(r#"BANDWIDTH=1280000,CODECS="avc1.640028,mp4a.40.2" , resolution=1920x1080,NAME="a=b""#),
+            vec![
+                ("BANDWIDTH".to_string(), "1280000".to_string()),
+                ("CODECS".to_string(), "avc1.640028,mp4a.40.2".to_string()),
+                ("RESOLUTION".to_string(), "1920x1080".to_string()),
+                ("NAME".to_string(), "a=b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute_list_tolerates_malformed_input() {
+        assert!(pairs("").is_empty());
+        assert_eq!(pairs("A=1,GARBAGE"), vec![("A".to_string(), "1".to_string())]);
+        assert_eq!(pairs(r#"URI="unterminated"#), vec![("URI".to_string(), "unterminated".to_string())]);
+        assert_eq!(pairs("A=,B=2"), vec![("A".to_string(), String::new()), ("B".to_string(), "2".to_string())]);
+    }
+
+    #[test]
+    fn stream_inf_fields() {
+        let variant = HlsVariant::from_stream_inf(
+            r#"PROGRAM-ID=1,BANDWIDTH=5000000,AVERAGE-BANDWIDTH=4000000,RESOLUTION=1920x1080,FRAME-RATE=29.970,CODECS="avc1.640028,mp4a.40.2""#,
+        );
+        assert_eq!(variant.bandwidth, Some(5_000_000));
+        assert_eq!(variant.average_bandwidth, Some(4_000_000));
+        assert_eq!(variant.resolution.as_deref(), Some("1920x1080"));
+        assert_eq!(variant.frame_rate, Some(29.97));
+        assert_eq!(variant.codecs.as_deref(), Some("avc1.640028,mp4a.40.2"));
+
+        let variant = HlsVariant::from_stream_inf("BANDWIDTH=abc");
+        assert_eq!(variant.bandwidth, None);
+    }
+
+    #[test]
+    fn segment_urls_and_media_tags() {
+        assert!(is_segment_url("http://a.com/seg-1.TS?token=1"));
+        assert!(is_segment_url("chunk.m4s"));
+        assert!(!is_segment_url("http://a.com/movie.mp4"));
+        assert!(!is_segment_url("http://a.com/song.mp3"));
+        assert!(!is_segment_url("http://a.com/index.m3u8"));
+        assert!(!is_segment_url("http://a.com/ts/index"));
+        assert!(is_media_playlist_tag("#EXT-X-TARGETDURATION:4"));
+        assert!(!is_media_playlist_tag("#EXT-X-STREAM-INF:BANDWIDTH=1"));
+    }
+
+    #[test]
+    fn rewrite_resolves_relative_root_relative_and_parent_paths() {
+        let content = "#EXTM3U\n#EXTINF:4,\nseg1.ts\n#EXTINF:4,\n/other/seg2.ts\n#EXTINF:4,\n../ch2/seg3.ts\n#EXTINF:4,\nhttps://b.example.com/seg4.ts";
+        let rewritten = rewrite_playlist(content, BASE, proxy);
+        let lines: Vec<&str> = rewritten.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "proxy(http://cdn.example.com/live/ch1/seg1.ts)",
+                "proxy(http://cdn.example.com/other/seg2.ts)",
+                "proxy(http://cdn.example.com/live/ch2/seg3.ts)",
+                "proxy(https://b.example.com/seg4.ts)",
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrite_uri_attributes_in_tags() {
+        let content = "#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\",IV=0x1\n\
+                       #EXT-X-MAP:URI=\"/init.mp4\"\n\
+                       #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"中文\",URI=\"audio/zh.m3u8\"\n\
+                       #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=1000,URI=\"iframe.m3u8\"\n\
+                       #EXT-X-TARGETDURATION:4";
+        let rewritten = rewrite_playlist(content, BASE, proxy);
+        let lines: Vec<&str> = rewritten.lines().collect();
+        assert_eq!(lines[0], "#EXT-X-KEY:METHOD=AES-128,URI=\"proxy(http://cdn.example.com/live/ch1/key.bin)\",IV=0x1");
+        assert_eq!(lines[1], "#EXT-X-MAP:URI=\"proxy(http://cdn.example.com/init.mp4)\"");
+        assert_eq!(
+            lines[2],
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"中文\",URI=\"proxy(http://cdn.example.com/live/ch1/audio/zh.m3u8)\""
+        );
+        assert_eq!(lines[3], "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=1000,URI=\"proxy(http://cdn.example.com/live/ch1/iframe.m3u8)\"");
+        assert_eq!(lines[4], "#EXT-X-TARGETDURATION:4");
+    }
+
+    #[test]
+    fn rewrite_leaves_other_attributes_alone() {
+        let content = "#EXT-X-SESSION-DATA:DATA-ID=\"x\",XURI=\"keep\"\n#EXT-X-KEY:METHOD=AES-128,URI=\"broken";
+        assert_eq!(rewrite_playlist(content, BASE, proxy), content);
+    }
+}
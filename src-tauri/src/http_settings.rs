@@ -0,0 +1,239 @@
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::HttpOptions;
+
+/// 未设置超时时间时使用的连接 / 读取超时（秒）
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// User-Agent 预设，部分服务商只允许特定播放器访问
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum UserAgentPreset {
+    /// 不设置，使用 HTTP 库的默认值
+    #[default]
+    Default,
+    Chrome,
+    Vlc,
+    Kodi,
+    Tivimate,
+    /// Android 上 TVBox 等应用使用的 okhttp
+    Okhttp,
+    Custom(String),
+}
+
+impl UserAgentPreset {
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            UserAgentPreset::Default => None,
+            UserAgentPreset::Chrome => Some("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"),
+            UserAgentPreset::Vlc => Some("VLC/3.0.20 LibVLC/3.0.20"),
+            UserAgentPreset::Kodi => Some("Kodi/21.0 (Windows NT 10.0; Win64; x64) App_Bitness/64 Version/21.0-(21.0.0)"),
+            UserAgentPreset::Tivimate => Some("TiviMate/5.0.4 (Android 11)"),
+            UserAgentPreset::Okhttp => Some("okhttp/4.12.0"),
+            UserAgentPreset::Custom(value) => Some(value.as_str()).filter(|v| !v.trim().is_empty()),
+        }
+    }
+}
+
+/// 订阅源的认证方式（Debug 输出不包含密码和令牌）
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpAuth {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+}
+
+impl std::fmt::Debug for HttpAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpAuth::Basic { username, .. } => f.debug_struct("Basic").field("username", username).field("password", &"***").finish(),
+            HttpAuth::Bearer { .. } => f.debug_struct("Bearer").field("token", &"***").finish(),
+        }
+    }
+}
+
+impl HttpAuth {
+    fn header_value(&self) -> String {
+        match self {
+            HttpAuth::Basic { username, password } => {
+                let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+                format!("Basic {}", credentials)
+            }
+            HttpAuth::Bearer { token } => format!("Bearer {}", token.trim()),
+        }
+    }
+}
+
+/// 订阅源的 HTTP 请求设置，下载播放列表时使用，可选地用于播放该订阅源的频道
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceHttpSettings {
+    #[serde(default)]
+    pub user_agent: UserAgentPreset,
+    /// 额外的请求头（Referer、Cookie 等）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<HttpAuth>,
+    /// 连接和读取超时（秒），为空时使用 30 秒
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// 接受无效的 HTTPS 证书（自签名证书等）
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    /// 播放该订阅源的频道时，通过本地代理附带相同的请求头和认证
+    #[serde(default)]
+    pub apply_to_streams: bool,
+}
+
+impl SourceHttpSettings {
+    pub fn is_default(&self) -> bool {
+        *self == SourceHttpSettings::default()
+    }
+
+    /// 只设置 User-Agent（TVBox 配置中直播条目的 `ua`）
+    pub fn with_user_agent(user_agent: Option<&str>) -> SourceHttpSettings {
+        SourceHttpSettings {
+            user_agent: user_agent.map(|ua| UserAgentPreset::Custom(ua.to_string())).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// 检查请求头名称和值是否合法，保存订阅源前调用
+    pub fn validate(&self) -> Result<(), String> {
+        self.header_map().map(|_| ())
+    }
+
+    /// User-Agent、额外请求头和认证组成的请求头
+    fn header_map(&self) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        if let Some(user_agent) = self.user_agent.value() {
+            let value = HeaderValue::from_str(user_agent).map_err(|_| format!("无效的 User-Agent: {}", user_agent))?;
+            headers.insert(USER_AGENT, value);
+        }
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| format!("无效的请求头名称: {}", name))?;
+            let header_value = HeaderValue::from_str(value.trim()).map_err(|_| format!("请求头 {} 的值无效", name))?;
+            headers.insert(header_name, header_value);
+        }
+        if let Some(auth) = &self.auth {
+            let mut value = HeaderValue::from_str(&auth.header_value()).map_err(|_| "认证信息包含无效字符".to_string())?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+
+    /// 按设置创建下载播放列表的客户端（请求头作为默认请求头）
+    pub fn client(&self) -> Result<reqwest::Client, String> {
+        let timeout = Duration::from_secs(self.timeout_secs.filter(|t| *t > 0).unwrap_or(DEFAULT_TIMEOUT_SECS));
+        reqwest::Client::builder()
+            .default_headers(self.header_map()?)
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .build()
+            .map_err(|e| format!("创建客户端失败: {}", e))
+    }
+
+    /// 合并到频道自带的 HTTP 选项中，用于代理播放；频道自带的请求头优先
+    pub fn merge_into_stream_options(&self, channel: HttpOptions) -> HttpOptions {
+        let mut headers = self.headers.clone();
+        if let Some(auth) = &self.auth {
+            headers.insert("Authorization".to_string(), auth.header_value());
+        }
+        headers.extend(channel.headers);
+
+        HttpOptions {
+            user_agent: channel.user_agent.or_else(|| self.user_agent.value().map(str::to_string)),
+            referrer: channel.referrer,
+            headers,
+            timeout_secs: channel.timeout_secs.or(self.timeout_secs),
+            accept_invalid_certs: channel.accept_invalid_certs || self.accept_invalid_certs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_headers(headers: &[(&str, &str)]) -> SourceHttpSettings {
+        SourceHttpSettings {
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        assert_eq!(with_headers(&[("Bad Name", "x")]).validate(), Err("无效的请求头名称: Bad Name".to_string()));
+        assert_eq!(with_headers(&[("Referer", "a\nb")]).validate(), Err("请求头 Referer 的值无效".to_string()));
+
+        let settings = SourceHttpSettings { user_agent: UserAgentPreset::Custom("ua\r\n".to_string()), ..Default::default() };
+        assert!(settings.validate().unwrap_err().starts_with("无效的 User-Agent"));
+
+        let settings = SourceHttpSettings { auth: Some(HttpAuth::Bearer { token: "a\nb".to_string() }), ..Default::default() };
+        assert_eq!(settings.validate(), Err("认证信息包含无效字符".to_string()));
+
+        assert!(with_headers(&[(" Referer ", " http://example.com/ ")]).validate().is_ok());
+    }
+
+    #[test]
+    fn auth_header_values() {
+        let basic = HttpAuth::Basic { username: "user".to_string(), password: "pass".to_string() };
+        assert_eq!(basic.header_value(), "Basic dXNlcjpwYXNz");
+        let bearer = HttpAuth::Bearer { token: " abc123 ".to_string() };
+        assert_eq!(bearer.header_value(), "Bearer abc123");
+
+        let settings = SourceHttpSettings { auth: Some(basic), ..Default::default() };
+        let headers = settings.header_map().unwrap();
+        assert!(headers[AUTHORIZATION].is_sensitive());
+        assert!(!headers.contains_key(USER_AGENT));
+    }
+
+    #[test]
+    fn debug_output_hides_credentials() {
+        let basic = format!("{:?}", HttpAuth::Basic { username: "user".to_string(), password: "secret".to_string() });
+        let bearer = format!("{:?}", HttpAuth::Bearer { token: "secret".to_string() });
+        assert!(basic.contains("user"));
+        assert!(!basic.contains("secret"));
+        assert!(!bearer.contains("secret"));
+    }
+
+    #[test]
+    fn channel_options_take_precedence_when_merging() {
+        let settings = SourceHttpSettings {
+            user_agent: UserAgentPreset::Vlc,
+            headers: [("Referer", "http://source/"), ("Cookie", "a=1")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            auth: Some(HttpAuth::Bearer { token: "t".to_string() }),
+            timeout_secs: Some(10),
+            accept_invalid_certs: true,
+            apply_to_streams: true,
+        };
+
+        let channel = HttpOptions {
+            headers: [("Cookie".to_string(), "b=2".to_string())].into_iter().collect(),
+            timeout_secs: Some(5),
+            ..Default::default()
+        };
+        let merged = settings.merge_into_stream_options(channel);
+        assert_eq!(merged.user_agent.as_deref(), UserAgentPreset::Vlc.value());
+        assert_eq!(merged.headers["Cookie"], "b=2");
+        assert_eq!(merged.headers["Referer"], "http://source/");
+        assert_eq!(merged.headers["Authorization"], "Bearer t");
+        assert_eq!(merged.timeout_secs, Some(5));
+        assert!(merged.accept_invalid_certs);
+
+        let channel = HttpOptions { user_agent: Some("Channel/1.0".to_string()), ..Default::default() };
+        let merged = settings.merge_into_stream_options(channel);
+        assert_eq!(merged.user_agent.as_deref(), Some("Channel/1.0"));
+        assert_eq!(merged.timeout_secs, Some(10));
+
+        let merged = SourceHttpSettings::default().merge_into_stream_options(HttpOptions::default());
+        assert_eq!(merged, HttpOptions::default());
+    }
+}
@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+use crate::diagnostics::{IssueKind, ParseReport};
+use crate::hls::{self, HlsVariant};
+use crate::playlist::Playlist;
+use crate::{Channel, HttpOptions, PlaylistMeta};
+
+/// `#EXTINF` 行的解析结果
+#[derive(Debug, Default)]
+pub struct ExtInf {
+    /// 时长（秒），直播列表通常为 -1
+    pub duration: Option<f64>,
+    /// 按出现顺序保存的属性
+    pub attributes: Vec<(String, String)>,
+    /// 逗号后面的标题
+    pub title: String,
+    /// 没有分隔标题的逗号
+    pub missing_comma: bool,
+}
+
+/// 解析 `key="value"` 形式的属性列表，直到遇到不在引号内的逗号
+///
+/// 支持双引号、单引号和无引号的值。返回属性列表以及逗号之后的剩余部分（如果有）。
+pub fn parse_attributes(input: &str) -> (Vec<(String, String)>, Option<&str>) {
+    let bytes = input.as_bytes();
+    let len = bytes.len();
+    let mut attributes = Vec::new();
+    let mut i = 0;
+
+    loop {
+        while i < len && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= len {
+            return (attributes, None);
+        }
+        if bytes[i] == b',' {
+            return (attributes, Some(&input[i + 1..]));
+        }
+
+        // 读取属性名
+        let key_start = i;
+        while i < len && !bytes[i].is_ascii_whitespace() && bytes[i] != b'=' && bytes[i] != b',' {
+            i += 1;
+        }
+        let key = &input[key_start..i];
+
+        if i < len && bytes[i] == b'=' {
+            i += 1;
+            let value = if i < len && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                // 引号内的值可以包含空格和逗号
+                let quote = bytes[i];
+                i += 1;
+                let value_start = i;
+                while i < len && bytes[i] != quote {
+                    i += 1;
+                }
+                let value = &input[value_start..i];
+                if i < len {
+                    i += 1; // 跳过结束引号
+                }
+                value
+            } else {
+                let value_start = i;
+                while i < len && !bytes[i].is_ascii_whitespace() && bytes[i] != b',' {
+                    i += 1;
+                }
+                &input[value_start..i]
+            };
+
+            if !key.is_empty() {
+                attributes.push((key.to_string(), value.to_string()));
+            }
+        } else if !key.is_empty() {
+            // 没有值的属性
+            attributes.push((key.to_string(), String::new()));
+        }
+    }
+}
+
+/// 解析一行 `#EXTINF:<duration> key="value" ...,<title>`
+pub fn parse_extinf(line: &str) -> Option<ExtInf> {
+    let info_part = line.trim().strip_prefix("#EXTINF:")?;
+
+    // 跳过时长字段（在第一个空白或逗号之前，直播通常为 -1）
+    let duration_end = info_part
+        .find(|c: char| c.is_whitespace() || c == ',')
+        .unwrap_or(info_part.len());
+
+    let (attributes, title) = parse_attributes(&info_part[duration_end..]);
+
+    Some(ExtInf {
+        duration: info_part[..duration_end].trim().parse().ok(),
+        attributes,
+        missing_comma: title.is_none(),
+        title: title.map(|t| t.trim().to_string()).unwrap_or_default(),
+    })
+}
+
+impl Channel {
+    /// 根据 `#EXTINF` 信息构建频道，常用属性写入对应字段，其余保存在 `attributes` 中
+    pub fn from_extinf(info: ExtInf, url: String) -> Channel {
+        let mut channel = Channel {
+            url,
+            ..Default::default()
+        };
+        let mut extra = HashMap::new();
+
+        for (key, value) in info.attributes {
+            match key.to_ascii_lowercase().as_str() {
+                "tvg-id" => channel.tvg_id = non_empty(value),
+                "tvg-name" => channel.tvg_name = non_empty(value),
+                "tvg-logo" => channel.logo = non_empty(value),
+                "group-title" => channel.group = non_empty(value),
+                "tvg-chno" => match value.trim().parse() {
+                    Ok(chno) => channel.tvg_chno = Some(chno),
+                    Err(_) => {
+                        extra.insert(key, value);
+                    }
+                },
+                "tvg-shift" => match value.trim().parse() {
+                    Ok(shift) => channel.tvg_shift = Some(shift),
+                    Err(_) => {
+                        extra.insert(key, value);
+                    }
+                },
+                "catchup" | "catchup-type" => {
+                    if channel.catchup.is_none() {
+                        channel.catchup = non_empty(value);
+                    }
+                }
+                "catchup-source" => channel.catchup_source = non_empty(value),
+                "catchup-days" => match value.trim().parse() {
+                    Ok(days) => channel.catchup_days = Some(days),
+                    Err(_) => {
+                        extra.insert(key, value);
+                    }
+                },
+                _ => {
+                    extra.insert(key, value);
+                }
+            }
+        }
+
+        channel.name = if !info.title.is_empty() {
+            info.title
+        } else if let Some(tvg_name) = &channel.tvg_name {
+            tvg_name.clone()
+        } else {
+            "未命名频道".to_string()
+        };
+        channel.attributes = extra;
+
+        channel
+    }
+}
+
+impl PlaylistMeta {
+    /// 合并一行 `#EXTM3U` 中的属性（有的列表会写多行头部）
+    pub fn apply_header_attributes(&mut self, attributes: Vec<(String, String)>) {
+        for (key, value) in attributes {
+            match key.to_ascii_lowercase().as_str() {
+                "x-tvg-url" | "url-tvg" => {
+                    for epg_url in value.split(',').map(str::trim).filter(|u| !u.is_empty()) {
+                        if !self.epg_urls.iter().any(|u| u == epg_url) {
+                            self.epg_urls.push(epg_url.to_string());
+                        }
+                    }
+                }
+                "catchup" | "catchup-type" => self.catchup = non_empty(value),
+                "catchup-source" => self.catchup_source = non_empty(value),
+                "catchup-days" => match value.trim().parse() {
+                    Ok(days) => self.catchup_days = Some(days),
+                    Err(_) => {
+                        self.attributes.insert(key, value);
+                    }
+                },
+                "tvg-shift" => match value.trim().parse() {
+                    Ok(shift) => self.tvg_shift = Some(shift),
+                    Err(_) => {
+                        self.attributes.insert(key, value);
+                    }
+                },
+                "user-agent" | "http-user-agent" => self.user_agent = non_empty(value),
+                _ => {
+                    self.attributes.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+/// 频道级别的播放选项（`#EXTVLCOPT` / `#KODIPROP`），可能出现在 `#EXTINF` 前后
+#[derive(Debug, Default)]
+struct PendingOptions {
+    http: HttpOptions,
+    vlc_options: HashMap<String, String>,
+    kodi_props: HashMap<String, String>,
+}
+
+impl PendingOptions {
+    fn is_empty(&self) -> bool {
+        self.http.is_empty() && self.vlc_options.is_empty() && self.kodi_props.is_empty()
+    }
+
+    /// `#EXTVLCOPT:http-user-agent=...`
+    fn add_vlc_option(&mut self, option: &str) {
+        let Some((key, value)) = option.split_once('=') else {
+            return;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().trim_matches('"').to_string();
+
+        match key.as_str() {
+            "http-user-agent" => self.http.user_agent = non_empty(value.clone()),
+            "http-referrer" | "http-referer" => self.http.referrer = non_empty(value.clone()),
+            "http-origin" => {
+                self.http.headers.insert("Origin".to_string(), value.clone());
+            }
+            "http-cookie" => {
+                self.http.headers.insert("Cookie".to_string(), value.clone());
+            }
+            _ => {}
+        }
+        self.vlc_options.insert(key, value);
+    }
+
+    /// `#KODIPROP:inputstream.adaptive.stream_headers=User-Agent=...&Referer=...`
+    fn add_kodi_prop(&mut self, prop: &str) {
+        let Some((key, value)) = prop.split_once('=') else {
+            return;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim().to_string();
+
+        if matches!(
+            key.as_str(),
+            "inputstream.adaptive.stream_headers"
+                | "inputstream.adaptive.manifest_headers"
+                | "inputstream.adaptive.common_headers"
+        ) {
+            for pair in value.split('&') {
+                let Some((name, header_value)) = pair.split_once('=') else {
+                    continue;
+                };
+                let header_value = urlencoding::decode(header_value)
+                    .map(|v| v.into_owned())
+                    .unwrap_or_else(|_| header_value.to_string());
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "user-agent" => self.http.user_agent = non_empty(header_value),
+                    "referer" | "referrer" => self.http.referrer = non_empty(header_value),
+                    _ => {
+                        self.http.headers.insert(name.trim().to_string(), header_value);
+                    }
+                }
+            }
+        }
+        self.kodi_props.insert(key, value);
+    }
+
+    fn apply_to(self, channel: &mut Channel) {
+        channel.http = self.http;
+        channel.vlc_options = self.vlc_options;
+        channel.kodi_props = self.kodi_props;
+    }
+}
+
+impl Channel {
+    /// 频道没有单独设置时继承播放列表级别的默认值
+    pub fn inherit_defaults(&mut self, meta: &PlaylistMeta) {
+        if self.catchup.is_none() && self.catchup_source.is_none() {
+            self.catchup = meta.catchup.clone();
+            self.catchup_source = meta.catchup_source.clone();
+        }
+        if self.catchup_days.is_none() {
+            self.catchup_days = meta.catchup_days;
+        }
+        if self.tvg_shift.is_none() {
+            self.tvg_shift = meta.tvg_shift;
+        }
+        if self.http.user_agent.is_none() {
+            self.http.user_agent = meta.user_agent.clone();
+        }
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// 按行拆分内容，兼容 BOM、CRLF 以及单独的 CR 换行（CRLF 算作一行，保证行号准确）
+pub fn split_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .flat_map(|line| line.split('\r'))
+        .map(str::trim)
+}
+
+/// 逐行解析 M3U，一次性解析和流式解析共用
+#[derive(Debug, Default)]
+pub struct M3uParser {
+    meta: PlaylistMeta,
+    channels: Vec<Channel>,
+    pending: Option<ExtInf>,
+    /// `pending` 所在的行号
+    pending_line: usize,
+    pending_options: PendingOptions,
+    /// `#EXTGRP` 指定的分组，用于下一个没有 group-title 的条目
+    pending_group: Option<String>,
+    /// 当前行号
+    line: usize,
+    report: ParseReport,
+    /// 出现了只属于 HLS 媒体播放列表的指令（#EXT-X-TARGETDURATION / #EXT-X-MAP 等）
+    hls_media: bool,
+    /// HLS 主播放列表中的清晰度
+    variants: Vec<HlsVariant>,
+    /// 等待下一行地址的 `#EXT-X-STREAM-INF`
+    pending_variant: Option<HlsVariant>,
+    /// 时长为正、地址为分片文件的条目数，没有媒体播放列表指令时据此判断
+    segment_entries: usize,
+}
+
+impl M3uParser {
+    /// 输入一行（已去掉换行符）
+    pub fn push_line(&mut self, line: &str) {
+        self.line += 1;
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+
+        // 记录 HLS 视频流的特征，结束时判断
+        if hls::is_media_playlist_tag(line) {
+            self.hls_media = true;
+        }
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            self.pending_variant = Some(HlsVariant::from_stream_inf(attributes));
+            return;
+        }
+
+        // 解析 #EXTM3U 头部属性
+        if let Some(header) = line.strip_prefix("#EXTM3U") {
+            let (attributes, _) = parse_attributes(header);
+            self.meta.apply_header_attributes(attributes);
+            return;
+        }
+
+        // 解析 #EXTINF 行
+        if line.starts_with("#EXTINF:") {
+            if let Some(previous) = self.pending.take() {
+                debug!("跳过没有 URL 的条目: {}", previous.title);
+                self.skip_pending(previous);
+            }
+            self.pending = parse_extinf(line);
+            self.pending_line = self.line;
+            if self.pending.as_ref().is_some_and(|info| info.missing_comma) {
+                self.report.add(self.line, IssueKind::Malformed, "#EXTINF 缺少分隔频道名的逗号");
+            }
+            return;
+        }
+
+        // 频道播放选项
+        if let Some(option) = line.strip_prefix("#EXTVLCOPT:") {
+            self.pending_options.add_vlc_option(option);
+            return;
+        }
+        if let Some(prop) = line.strip_prefix("#KODIPROP:") {
+            self.pending_options.add_kodi_prop(prop);
+            return;
+        }
+        if let Some(group) = line.strip_prefix("#EXTGRP:") {
+            self.pending_group = non_empty(group.to_string());
+            return;
+        }
+
+        // 其他指令或注释；HLS 指令在结束时统一判断
+        if line.starts_with('#') {
+            if line.starts_with("#EXT") && !line.starts_with("#EXT-X-") {
+                let directive = line.split(':').next().unwrap_or(line);
+                self.report.unsupported_directive(self.line, directive);
+            }
+            return;
+        }
+
+        // 主播放列表中清晰度的地址
+        if let Some(mut variant) = self.pending_variant.take() {
+            variant.url = line.to_string();
+            self.variants.push(variant);
+            return;
+        }
+
+        // 这是一个 URL 行，归属于前面的 #EXTINF
+        let Some(info) = self.pending.take() else {
+            self.report.add(self.line, IssueKind::Skipped, format!("地址前没有 #EXTINF: {}", line));
+            // 播放选项和分组属于被跳过的地址，不能留给下一个频道
+            self.pending_options = PendingOptions::default();
+            self.pending_group = None;
+            return;
+        };
+        let missing_name = info.title.is_empty()
+            && !info.attributes.iter().any(|(key, value)| key.eq_ignore_ascii_case("tvg-name") && !value.trim().is_empty());
+        if missing_name {
+            self.report.add(self.pending_line, IssueKind::MissingName, "频道没有名称");
+        }
+        if !line.contains("://") {
+            self.report.add(self.line, IssueKind::Malformed, format!("地址缺少协议（如 http://）: {}", line));
+        }
+        self.report.check_duplicate_url(self.line, line);
+        if info.duration.is_some_and(|d| d > 0.0) && hls::is_segment_url(line) {
+            self.segment_entries += 1;
+        }
+
+        let mut channel = Channel::from_extinf(info, line.to_string());
+        if let Some(group) = self.pending_group.take() {
+            channel.group.get_or_insert(group);
+        }
+        if !self.pending_options.is_empty() {
+            debug!("频道 {} 带有自定义播放选项", channel.name);
+            std::mem::take(&mut self.pending_options).apply_to(&mut channel);
+        }
+
+        // 检测并记录 IPv6 URL
+        if channel.url.contains('[') && channel.url.contains(']') {
+            debug!("检测到 IPv6 频道: {}", channel.name);
+        }
+
+        self.channels.push(channel);
+    }
+
+    /// HLS 主播放列表：清晰度按码率从高到低排列，相对地址按主播放列表地址解析
+    fn master_playlist(url: &str, mut variants: Vec<HlsVariant>) -> Playlist {
+        for variant in &mut variants {
+            variant.url = hls::resolve_url(url, &variant.url);
+        }
+        variants.sort_by_key(|variant| std::cmp::Reverse(variant.bandwidth));
+        info!("识别为 HLS 主播放列表，{} 个清晰度", variants.len());
+
+        Playlist::from_channels(vec![Channel {
+            name: hls::channel_name_from_url(url),
+            url: url.to_string(),
+            group: Some("视频流".to_string()),
+            variants,
+            ..Default::default()
+        }])
+    }
+
+    /// 记录一个没有地址的 `#EXTINF` 条目，丢弃它的播放选项和分组
+    fn skip_pending(&mut self, info: ExtInf) {
+        self.pending_options = PendingOptions::default();
+        self.pending_group = None;
+        let name = if info.title.is_empty() { "未命名" } else { info.title.as_str() };
+        self.report.add(self.pending_line, IssueKind::Skipped, format!("#EXTINF 后面没有播放地址: {}", name));
+    }
+
+    /// 已解析的频道数
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn finish(self, url: &str) -> Result<Playlist, String> {
+        // 检查是否是 HLS 视频流（而不是频道列表），作为单个频道返回
+        if !self.variants.is_empty() {
+            return Ok(Self::master_playlist(url, self.variants));
+        }
+        if self.hls_media || (!self.channels.is_empty() && self.segment_entries == self.channels.len()) {
+            info!("识别为 HLS 媒体播放列表");
+            return Ok(Playlist::from_channels(vec![Channel {
+                name: hls::channel_name_from_url(url),
+                url: url.to_string(),
+                group: Some("视频流".to_string()),
+                ..Default::default()
+            }]));
+        }
+
+        let mut parser = self;
+        if let Some(pending) = parser.pending.take() {
+            parser.skip_pending(pending);
+        }
+        let M3uParser { meta, mut channels, mut report, line, .. } = parser;
+        report.finish(line);
+
+        if channels.is_empty() {
+            warn!("未找到有效的频道信息");
+            if report.is_empty() {
+                Err("未找到有效的频道信息".to_string())
+            } else {
+                Err(format!("未找到有效的频道信息（{}）", report.summary()))
+            }
+        } else {
+            if !report.is_empty() {
+                warn!("播放列表解析问题: {}", report.summary());
+            }
+            for channel in &mut channels {
+                channel.inherit_defaults(&meta);
+            }
+            info!("成功解析 {} 个频道，EPG 地址: {:?}", channels.len(), meta.epg_urls);
+            Ok(Playlist {
+                meta,
+                channels,
+                encoding: None,
+                report,
+            })
+        }
+    }
+}
+
+pub fn parse_m3u_content(content: &str, url: &str) -> Result<Playlist, String> {
+    let mut parser = M3uParser::default();
+    for line in split_lines(content) {
+        parser.push_line(line);
+    }
+    parser.finish(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute<'a>(info: &'a ExtInf, key: &str) -> Option<&'a str> {
+        info.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn title_may_contain_commas_and_quoted_values_may_too() {
+        let info = parse_extinf(r#"#EXTINF:-1 tvg-name="A, B" group-title="新闻,综合",CCTV-1, 综合"#).unwrap();
+        assert_eq!(info.duration, Some(-1.0));
+        assert_eq!(attribute(&info, "tvg-name"), Some("A, B"));
+        assert_eq!(attribute(&info, "group-title"), Some("新闻,综合"));
+        assert_eq!(info.title, "CCTV-1, 综合");
+        assert!(!info.missing_comma);
+    }
+
+    #[test]
+    fn unquoted_and_single_quoted_values() {
+        let info = parse_extinf("#EXTINF:-1 tvg-id=cctv1 tvg-chno=1 tvg-logo='http://logo/a b.png' radio,CCTV1").unwrap();
+        assert_eq!(attribute(&info, "tvg-id"), Some("cctv1"));
+        assert_eq!(attribute(&info, "tvg-chno"), Some("1"));
+        assert_eq!(attribute(&info, "tvg-logo"), Some("http://logo/a b.png"));
+        assert_eq!(attribute(&info, "radio"), Some(""));
+        assert_eq!(info.title, "CCTV1");
+    }
+
+    #[test]
+    fn unquoted_value_ends_at_comma() {
+        let info = parse_extinf("#EXTINF:-1 tvg-id=cctv1,CCTV1").unwrap();
+        assert_eq!(attribute(&info, "tvg-id"), Some("cctv1"));
+        assert_eq!(info.title, "CCTV1");
+    }
+
+    #[test]
+    fn missing_comma_and_unterminated_quote() {
+        let info = parse_extinf(r#"#EXTINF:-1 tvg-name="CCTV1"#).unwrap();
+        assert!(info.missing_comma);
+        assert_eq!(info.title, "");
+        assert_eq!(attribute(&info, "tvg-name"), Some("CCTV1"));
+
+        let info = parse_extinf("#EXTINF:10.5,").unwrap();
+        assert_eq!(info.duration, Some(10.5));
+        assert!(info.attributes.is_empty());
+        assert_eq!(info.title, "");
+
+        assert!(parse_extinf("#EXTVLCOPT:http-user-agent=x").is_none());
+    }
+
+    #[test]
+    fn typed_fields_and_unknown_attributes() {
+        let info = parse_extinf(r#"#EXTINF:-1 tvg-id="c1" tvg-chno="x" tvg-shift="-1.5" catchup="append" CUSTOM="v",名称"#).unwrap();
+        let channel = Channel::from_extinf(info, "http://a/1".to_string());
+        assert_eq!(channel.tvg_id.as_deref(), Some("c1"));
+        assert_eq!(channel.tvg_chno, None);
+        assert_eq!(channel.attributes.get("tvg-chno").map(String::as_str), Some("x"));
+        assert_eq!(channel.tvg_shift, Some(-1.5));
+        assert_eq!(channel.catchup.as_deref(), Some("append"));
+        assert_eq!(channel.attributes.get("CUSTOM").map(String::as_str), Some("v"));
+        assert_eq!(channel.name, "名称");
+    }
+
+    #[test]
+    fn header_attributes_are_inherited() {
+        let content = "#EXTM3U x-tvg-url=\"http://e/1.xml, http://e/2.xml\" url-tvg=\"http://e/1.xml\" catchup=\"append\" catchup-source=\"?s={utc}\" tvg-shift=8 user-agent=\"UA\" x-custom=1\n\
+                       #EXTINF:-1,CCTV1\nhttp://a/1\n\
+                       #EXTINF:-1 catchup=\"shift\" tvg-shift=\"0\",CCTV2\nhttp://a/2\n";
+        let playlist = parse_m3u_content(content, "http://a/list.m3u").unwrap();
+        assert_eq!(playlist.meta.epg_urls, vec!["http://e/1.xml", "http://e/2.xml"]);
+        assert_eq!(playlist.meta.attributes.get("x-custom").map(String::as_str), Some("1"));
+
+        let first = &playlist.channels[0];
+        assert_eq!(first.catchup.as_deref(), Some("append"));
+        assert_eq!(first.catchup_source.as_deref(), Some("?s={utc}"));
+        assert_eq!(first.tvg_shift, Some(8.0));
+        assert_eq!(first.http.user_agent.as_deref(), Some("UA"));
+
+        let second = &playlist.channels[1];
+        assert_eq!(second.catchup.as_deref(), Some("shift"));
+        assert_eq!(second.catchup_source, None);
+        assert_eq!(second.tvg_shift, Some(0.0));
+    }
+
+    #[test]
+    fn bom_crlf_and_bare_cr() {
+        let content = "\u{feff}#EXTM3U\r\n#EXTINF:-1 group-title=\"央视\",CCTV1\r\nhttp://a/1\r\n#EXTINF:-1,CCTV2\rhttp://a/2\r";
+        let playlist = parse_m3u_content(content, "list.m3u").unwrap();
+        let names: Vec<&str> = playlist.channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["CCTV1", "CCTV2"]);
+        assert_eq!(playlist.channels[0].group.as_deref(), Some("央视"));
+        assert_eq!(playlist.channels[1].url, "http://a/2");
+        assert!(playlist.report.is_empty());
+    }
+
+    #[test]
+    fn trailing_garbage_is_reported_not_imported() {
+        let content = "#EXTM3U\n#EXTINF:-1,CCTV1\nhttp://a/1\nsome garbage\n#EXTINF:-1,没有地址\n";
+        let playlist = parse_m3u_content(content, "list.m3u").unwrap();
+        assert_eq!(playlist.channels.len(), 1);
+        assert_eq!(playlist.report.count(IssueKind::Skipped), 2);
+        let lines: Vec<usize> = playlist.report.issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, vec![4, 5]);
+    }
+
+    #[test]
+    fn channel_options_before_and_after_extinf() {
+        let content = "#EXTM3U\n#EXTVLCOPT:http-user-agent=VLC\n#EXTINF:-1,A\n#EXTVLCOPT:http-referrer=http://r/\nhttp://a/1\n\
+                       #EXTINF:-1,B\n#KODIPROP:inputstream.adaptive.stream_headers=User-Agent=Kodi&Cookie=a%3Db\nhttp://a/2\n#EXTINF:-1,C\nhttp://a/3\n";
+        let playlist = parse_m3u_content(content, "list.m3u").unwrap();
+        let [a, b, c] = &playlist.channels[..] else {
+            panic!("应该有 3 个频道");
+        };
+        assert_eq!(a.http.user_agent.as_deref(), Some("VLC"));
+        assert_eq!(a.http.referrer.as_deref(), Some("http://r/"));
+        assert_eq!(b.http.user_agent.as_deref(), Some("Kodi"));
+        assert_eq!(b.http.headers.get("Cookie").map(String::as_str), Some("a=b"));
+        assert!(c.http.is_empty());
+    }
+
+    #[test]
+    fn options_of_skipped_entries_are_dropped() {
+        let content = "#EXTM3U\n#EXTINF:-1,没有地址\n#EXTVLCOPT:http-user-agent=Skipped\n\
+                       #EXTINF:-1,A\nhttp://a/1\n\
+                       #KODIPROP:inputstream.adaptive.license_type=clearkey\n#EXTVLCOPT:http-referrer=http://r/\nhttp://orphan/1\n\
+                       #EXTINF:-1,B\nhttp://a/2\n";
+        let playlist = parse_m3u_content(content, "list.m3u").unwrap();
+        let [a, b] = &playlist.channels[..] else {
+            panic!("应该有 2 个频道");
+        };
+        assert!(a.http.is_empty());
+        assert!(a.vlc_options.is_empty());
+        assert!(b.http.is_empty());
+        assert!(b.kodi_props.is_empty());
+        assert_eq!(playlist.report.count(IssueKind::Skipped), 2);
+    }
+
+    #[test]
+    fn extgrp_sets_group_of_next_entry() {
+        let content = "#EXTM3U\n#EXTINF:-1,A\n#EXTGRP:新闻\nhttp://a/1\n#EXTGRP:体育\n#EXTINF:-1 group-title=\"央视\",B\nhttp://a/2\n\
+                       #EXTINF:-1,C\nhttp://a/3\n";
+        let playlist = parse_m3u_content(content, "list.m3u").unwrap();
+        let groups: Vec<Option<&str>> = playlist.channels.iter().map(|c| c.group.as_deref()).collect();
+        assert_eq!(groups, [Some("新闻"), Some("央视"), None]);
+        assert!(playlist.report.unsupported_directives.is_empty());
+    }
+
+    #[test]
+    fn master_playlist_becomes_one_channel_with_variants() {
+        let content = "#EXTM3U\n#EXT-X-VERSION:3\n\
+                       #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360\nlow/index.m3u8\n\
+                       #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080,CODECS=\"avc1.640028,mp4a.40.2\"\nhttps://cdn.example/high/index.m3u8\n";
+        let playlist = parse_m3u_content(content, "http://a.com/live/cctv1/master.m3u8").unwrap();
+        let [channel] = &playlist.channels[..] else {
+            panic!("应该只有 1 个频道");
+        };
+        assert_eq!(channel.name, "cctv1");
+        assert_eq!(channel.url, "http://a.com/live/cctv1/master.m3u8");
+        let variants: Vec<(&str, Option<u64>)> = channel.variants.iter().map(|v| (v.url.as_str(), v.bandwidth)).collect();
+        assert_eq!(
+            variants,
+            [
+                ("https://cdn.example/high/index.m3u8", Some(5_000_000)),
+                ("http://a.com/live/cctv1/low/index.m3u8", Some(800_000)),
+            ]
+        );
+        assert_eq!(channel.variants[0].codecs.as_deref(), Some("avc1.640028,mp4a.40.2"));
+    }
+
+    #[test]
+    fn media_playlists_become_one_channel() {
+        let fmp4 = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:4\n#EXT-X-MAP:URI=\"init.mp4\"\n\
+                    #EXTINF:4.0,\nseg1.mp4\n#EXTINF:4.0,\nseg2.mp4\n";
+        let playlist = parse_m3u_content(fmp4, "http://a.com/live/news/index.m3u8").unwrap();
+        assert_eq!(playlist.channels.len(), 1);
+        assert_eq!(playlist.channels[0].url, "http://a.com/live/news/index.m3u8");
+        assert_eq!(playlist.channels[0].group.as_deref(), Some("视频流"));
+
+        // 没有媒体播放列表指令，但全部是 .ts / .m4s 分片
+        let segments = "#EXTM3U\n#EXTINF:10,\nhttp://a.com/1.ts\n#EXTINF:10,\nhttp://a.com/2.m4s?t=1\n";
+        assert_eq!(parse_m3u_content(segments, "http://a.com/stream").unwrap().channels.len(), 1);
+    }
+
+    #[test]
+    fn vod_and_music_lists_keep_their_entries() {
+        let content = "#EXTM3U\n#EXTINF:5400,电影 A\nhttp://a.com/movies/a.mp4\n#EXTINF:6000,电影 B\nhttp://a.com/movies/b.mp4\n\
+                       #EXTINF:215,歌曲\nhttp://a.com/music/song.mp3\n";
+        for url in ["", "/home/user/vod.m3u", "http://a.com/vod.m3u"] {
+            let playlist = parse_m3u_content(content, url).unwrap();
+            let names: Vec<&str> = playlist.channels.iter().map(|c| c.name.as_str()).collect();
+            assert_eq!(names, ["电影 A", "电影 B", "歌曲"]);
+            assert_eq!(playlist.channels[0].url, "http://a.com/movies/a.mp4");
+        }
+    }
+
+    #[test]
+    fn empty_playlist_is_an_error() {
+        assert!(parse_m3u_content("#EXTM3U\n", "list.m3u").is_err());
+    }
+}
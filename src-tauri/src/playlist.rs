@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+use crate::charset::{self, LineDecoder};
+use crate::diagnostics::ParseReport;
+use crate::m3u::{self, M3uParser};
+use crate::txt::{self, TxtParser};
+use crate::{Channel, PlaylistMeta};
+
+/// 播放列表解析结果
+#[derive(Debug, Default)]
+pub struct Playlist {
+    pub meta: PlaylistMeta,
+    pub channels: Vec<Channel>,
+    /// 原始内容的编码（从文本解析时为空）
+    pub encoding: Option<String>,
+    /// 解析过程中跳过或不规范的条目
+    pub report: ParseReport,
+}
+
+impl Playlist {
+    pub fn from_channels(channels: Vec<Channel>) -> Playlist {
+        Playlist {
+            meta: PlaylistMeta::default(),
+            channels,
+            encoding: None,
+            report: ParseReport::default(),
+        }
+    }
+
+    /// 合并重复频道：按 tvg-id（没有则按规范化的名称）分组，
+    /// 保留第一次出现的频道，后面出现的地址按顺序追加为备用地址
+    ///
+    /// 备用地址使用主地址的请求头和播放选项，选项不同的条目不合并。
+    pub fn merge_duplicates(&mut self) {
+        let before = self.channels.len();
+        let mut merged: Vec<Channel> = Vec::with_capacity(before);
+        let mut indexes_by_key: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for channel in self.channels.drain(..) {
+            let indexes = indexes_by_key.entry(channel.merge_key()).or_default();
+            match indexes.iter().copied().find(|&index| merged[index].same_options(&channel)) {
+                Some(index) => merged[index].absorb(channel),
+                None => {
+                    indexes.push(merged.len());
+                    merged.push(channel);
+                }
+            }
+        }
+
+        if merged.len() < before {
+            info!("合并重复频道: {} -> {}", before, merged.len());
+        }
+        self.channels = merged;
+    }
+}
+
+/// 支持的播放列表格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// 扩展 M3U（也包括直接填写的 HLS 流地址）
+    M3u,
+    /// DIYP / TXT 格式：`分组,#genre#` + `频道名,URL`
+    Txt,
+}
+
+/// 根据内容判断播放列表格式
+pub fn detect_format(content: &str) -> PlaylistFormat {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with("#EXTM3U") || content.contains("#EXTINF") {
+        return PlaylistFormat::M3u;
+    }
+    if content.contains("#genre#") {
+        return PlaylistFormat::Txt;
+    }
+
+    // `频道名,scheme://...` 形式的行
+    let looks_like_txt = m3u::split_lines(content)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .take(20)
+        .any(|line| {
+            line.split_once(',')
+                .is_some_and(|(_, url)| url.trim().contains("://"))
+        });
+    if looks_like_txt {
+        PlaylistFormat::Txt
+    } else {
+        PlaylistFormat::M3u
+    }
+}
+
+/// 自动识别格式并解析播放列表内容
+pub fn parse_playlist_content(content: &str, url: &str) -> Result<Playlist, String> {
+    let format = detect_format(content);
+    debug!("播放列表格式: {:?}", format);
+    match format {
+        PlaylistFormat::M3u => m3u::parse_m3u_content(content, url),
+        PlaylistFormat::Txt => txt::parse_txt_content(content),
+    }
+}
+
+/// 识别出格式之前最多缓存的非空、非注释行数（与 `detect_format` 检查的行数相同），超过后按已有内容判断
+const DETECT_LINE_LIMIT: usize = 20;
+
+/// 流式解析的结果
+pub enum ParsedContent {
+    Playlist(Box<Playlist>),
+    /// 不是播放列表（例如 TVBox JSON 配置），原样返回完整内容
+    Document(String),
+}
+
+enum ParserState {
+    /// 还没有识别出格式，先缓存前面的行
+    Detecting(Vec<String>),
+    M3u(Box<M3uParser>),
+    Txt(TxtParser),
+    Document(String),
+}
+
+/// 流式播放列表解析器：按块输入原始字节，凑成完整的行、转为 UTF-8 后交给对应格式的解析器
+///
+/// 只在内存中保留解析结果和不完整的最后一行，适合数万条目的大列表。
+pub struct StreamingParser {
+    url: String,
+    /// 尚未遇到换行符的字节
+    partial: Vec<u8>,
+    /// 上一个换行符是 `\r`，紧跟的 `\n` 属于同一个 CRLF
+    after_cr: bool,
+    decoder: LineDecoder,
+    state: ParserState,
+    /// 是否已经检查过开头的 BOM
+    bom_checked: bool,
+    /// UTF-16 内容无法按字节拆行，只能整体缓存后转码
+    utf16: Option<Vec<u8>>,
+}
+
+impl StreamingParser {
+    /// `content_type` 为响应的 Content-Type，用于读取声明的 charset
+    pub fn new(url: &str, content_type: Option<&str>) -> StreamingParser {
+        StreamingParser {
+            url: url.to_string(),
+            partial: Vec::new(),
+            after_cr: false,
+            decoder: LineDecoder::new(content_type),
+            state: ParserState::Detecting(Vec::new()),
+            bom_checked: false,
+            utf16: None,
+        }
+    }
+
+    /// 输入一块数据（换行符 `\n` / `\r` 在 UTF-8 和 GBK 中都不会出现在多字节字符内部）
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if let Some(buffer) = &mut self.utf16 {
+            buffer.extend_from_slice(chunk);
+            return;
+        }
+
+        if !self.bom_checked {
+            // 凑够 BOM 的长度再判断
+            self.partial.extend_from_slice(chunk);
+            if self.partial.len() < 3 {
+                return;
+            }
+            self.check_bom();
+            if let Some(buffer) = &mut self.utf16 {
+                buffer.append(&mut self.partial);
+                return;
+            }
+            let buffered = std::mem::take(&mut self.partial);
+            self.feed(&buffered);
+            return;
+        }
+
+        let mut rest = chunk;
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n' || b == b'\r') {
+            let after_cr = std::mem::replace(&mut self.after_cr, rest[pos] == b'\r');
+            if pos == 0 && after_cr && rest[0] == b'\n' {
+                rest = &rest[1..];
+                continue;
+            }
+            if self.partial.is_empty() {
+                self.push_bytes(&rest[..pos]);
+            } else {
+                let mut line = std::mem::take(&mut self.partial);
+                line.extend_from_slice(&rest[..pos]);
+                self.push_bytes(&line);
+            }
+            rest = &rest[pos + 1..];
+        }
+        if !rest.is_empty() {
+            self.after_cr = false;
+        }
+        self.partial.extend_from_slice(rest);
+    }
+
+    /// 已解析的频道数
+    pub fn channel_count(&self) -> usize {
+        match &self.state {
+            ParserState::M3u(parser) => parser.channel_count(),
+            ParserState::Txt(parser) => parser.channel_count(),
+            ParserState::Detecting(_) | ParserState::Document(_) => 0,
+        }
+    }
+
+    pub fn finish(mut self) -> Result<ParsedContent, String> {
+        if !self.bom_checked {
+            self.check_bom();
+        }
+        if let Some(buffer) = self.utf16.take() {
+            let (text, _) = charset::decode(&buffer, None);
+            for line in m3u::split_lines(&text) {
+                Self::push_to(&mut self.state, line);
+            }
+        } else if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.push_bytes(&line);
+        }
+        let state = &mut self.state;
+        self.decoder.finish(&mut |line| Self::push_to(state, line));
+
+        let encoding = self.decoder.encoding().name();
+        debug!("播放列表编码: {}", encoding);
+
+        let state = match self.state {
+            ParserState::Detecting(lines) => {
+                let format = detect_format(&lines.join("\n"));
+                let mut state = Self::start(format);
+                for line in &lines {
+                    Self::push_to(&mut state, line);
+                }
+                state
+            }
+            state => state,
+        };
+
+        let playlist = match state {
+            ParserState::M3u(parser) => parser.finish(&self.url)?,
+            ParserState::Txt(parser) => parser.finish()?,
+            ParserState::Document(content) => return Ok(ParsedContent::Document(content)),
+            ParserState::Detecting(_) => unreachable!(),
+        };
+        Ok(ParsedContent::Playlist(Box::new(Playlist {
+            encoding: Some(encoding.to_string()),
+            ..playlist
+        })))
+    }
+
+    fn check_bom(&mut self) {
+        self.bom_checked = true;
+        if let Some((encoding, bom_len)) = Encoding::for_bom(&self.partial) {
+            debug!("检测到 BOM: {}", encoding.name());
+            self.decoder.set_encoding(encoding);
+            if encoding == UTF_16LE || encoding == UTF_16BE {
+                // 保留 BOM，整体转码时再识别
+                self.utf16 = Some(Vec::new());
+            } else {
+                self.partial.drain(..bom_len);
+            }
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let state = &mut self.state;
+        self.decoder.push(bytes, &mut |line| Self::push_to(state, line));
+    }
+
+    fn start(format: PlaylistFormat) -> ParserState {
+        debug!("播放列表格式: {:?}", format);
+        match format {
+            PlaylistFormat::M3u => ParserState::M3u(Box::default()),
+            PlaylistFormat::Txt => ParserState::Txt(TxtParser::default()),
+        }
+    }
+
+    /// 把一行交给当前格式的解析器，格式未确定时先识别格式
+    fn push_to(state: &mut ParserState, line: &str) {
+        if let ParserState::Detecting(lines) = state {
+            let trimmed = line.trim();
+            let decided = if trimmed.is_empty() {
+                None
+            } else if lines.iter().all(|l| l.trim().is_empty()) && (trimmed.starts_with('{') || trimmed.starts_with("//")) {
+                // JSON 配置需要完整内容才能解析
+                Some(ParserState::Document(String::new()))
+            } else if trimmed.starts_with("#EXTM3U") || trimmed.starts_with("#EXTINF") {
+                Some(Self::start(PlaylistFormat::M3u))
+            } else if trimmed.contains("#genre#")
+                || (!trimmed.starts_with('#') && trimmed.split_once(',').is_some_and(|(_, url)| url.trim().contains("://")))
+            {
+                Some(Self::start(PlaylistFormat::Txt))
+            } else if lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')).count() >= DETECT_LINE_LIMIT {
+                let mut buffered = lines.join("\n");
+                buffered.push('\n');
+                buffered.push_str(line);
+                Some(Self::start(detect_format(&buffered)))
+            } else {
+                None
+            };
+
+            let Some(mut decided) = decided else {
+                lines.push(line.to_string());
+                return;
+            };
+            debug!("流式解析识别格式完成");
+            for buffered in lines.iter() {
+                Self::push_to(&mut decided, buffered);
+            }
+            *state = decided;
+        }
+
+        match state {
+            ParserState::M3u(parser) => parser.push_line(line),
+            ParserState::Txt(parser) => parser.push_line(line),
+            ParserState::Document(content) => {
+                content.push_str(line);
+                content.push('\n');
+            }
+            ParserState::Detecting(_) => unreachable!(),
+        }
+    }
+}
+
+impl Channel {
+    /// 判断重复频道使用的键
+    fn merge_key(&self) -> String {
+        if let Some(tvg_id) = &self.tvg_id {
+            return format!("id:{}", tvg_id.to_lowercase());
+        }
+        let normalized: String = self
+            .name
+            .chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '_'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        format!("name:{}", normalized)
+    }
+
+    /// 播放时使用的请求头和选项是否相同
+    fn same_options(&self, other: &Channel) -> bool {
+        self.http == other.http && self.vlc_options == other.vlc_options && self.kodi_props == other.kodi_props
+    }
+
+    /// 把重复频道的地址追加为备用地址，并补充缺失的台标
+    fn absorb(&mut self, other: Channel) {
+        for url in std::iter::once(other.url).chain(other.backup_urls) {
+            if url != self.url && !self.backup_urls.contains(&url) {
+                self.backup_urls.push(url);
+            }
+        }
+        if self.logo.is_none() {
+            self.logo = other.logo;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// B-1.m3u 中 CCTV4、CCTV5+、CCTV11 各出现两次，地址来自不同的上游
+    const DUPLICATES: &str = "#EXTM3U
+#EXTINF:-1 tvg-name=\"CCTV4\" tvg-logo=\"https://live.fanmingming.cn/tv/CCTV4.png\" group-title=\"央视频道\",CCTV4
+http://[2409:8087:1:20:20::29]/PLTV/88888888/224/3221226470/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV4\" tvg-logo=\"https://live.fanmingming.cn/tv/CCTV4.png\" group-title=\"央视频道\",CCTV4
+http://[2409:8087:1:20:20::2c]/PLTV/88888888/224/3221226454/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV5+\" group-title=\"央视频道\",CCTV5+
+http://[2409:8087:1:20:20::2c]/PLTV/88888888/224/3221226458/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV5+\" tvg-logo=\"https://live.fanmingming.cn/tv/CCTV5+.png\" group-title=\"央视频道\",CCTV5+
+http://[2409:8087:1:20:20::29]/PLTV/88888888/224/3221226449/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV11\" group-title=\"央视频道\",CCTV11
+http://[2409:8087:1:20:20::26]/PLTV/88888888/224/3221226334/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV11\" group-title=\"央视频道\",CCTV 11
+http://[2409:8087:1:20:20::26]/PLTV/88888888/224/3221226334/1.m3u8
+#EXTINF:-1 tvg-name=\"CCTV1\" group-title=\"央视频道\",CCTV1
+http://example.com/cctv1.m3u8
+";
+
+    #[test]
+    fn merges_duplicates_into_backup_urls() {
+        let mut playlist = parse_playlist_content(DUPLICATES, "B-1.m3u").unwrap();
+        assert_eq!(playlist.channels.len(), 7);
+        playlist.merge_duplicates();
+
+        let names: Vec<&str> = playlist.channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["CCTV4", "CCTV5+", "CCTV11", "CCTV1"]);
+
+        let cctv4 = &playlist.channels[0];
+        assert_eq!(cctv4.url, "http://[2409:8087:1:20:20::29]/PLTV/88888888/224/3221226470/1.m3u8");
+        assert_eq!(cctv4.backup_urls, ["http://[2409:8087:1:20:20::2c]/PLTV/88888888/224/3221226454/1.m3u8"]);
+
+        // 缺失的台标由后面的重复条目补充
+        let cctv5_plus = &playlist.channels[1];
+        assert_eq!(cctv5_plus.backup_urls.len(), 1);
+        assert_eq!(cctv5_plus.logo.as_deref(), Some("https://live.fanmingming.cn/tv/CCTV5+.png"));
+
+        // 相同的地址只保留一次
+        assert!(playlist.channels[2].backup_urls.is_empty());
+        assert!(playlist.channels[3].backup_urls.is_empty());
+    }
+
+    #[test]
+    fn keeps_duplicates_with_different_options_apart() {
+        let content = "#EXTM3U
+#EXTINF:-1,CCTV4
+#EXTVLCOPT:http-user-agent=PlayerA
+http://a.com/cctv4.m3u8
+#EXTINF:-1,CCTV4
+#EXTVLCOPT:http-user-agent=PlayerB
+http://b.com/cctv4.m3u8
+#EXTINF:-1,CCTV4
+#EXTVLCOPT:http-user-agent=PlayerA
+http://c.com/cctv4.m3u8
+";
+        let mut playlist = parse_playlist_content(content, "test.m3u").unwrap();
+        playlist.merge_duplicates();
+
+        assert_eq!(playlist.channels.len(), 2);
+        assert_eq!(playlist.channels[0].url, "http://a.com/cctv4.m3u8");
+        assert_eq!(playlist.channels[0].backup_urls, ["http://c.com/cctv4.m3u8"]);
+        assert_eq!(playlist.channels[1].url, "http://b.com/cctv4.m3u8");
+        assert!(playlist.channels[1].backup_urls.is_empty());
+    }
+
+    const M3U: &str = "\u{feff}#EXTM3U x-tvg-url=\"http://e/1.xml\"\r\n\
+                       #EXTINF:-1 tvg-id=\"cctv1\" group-title=\"央视频道\",CCTV-1 综合\r\n\
+                       #EXTVLCOPT:http-user-agent=VLC\r\nhttp://a.com/1.m3u8\r\n\
+                       #EXTINF:-1 group-title=\"卫视频道\",湖南卫视\rhttp://a.com/hunan.m3u8\r\
+                       #EXTINF:-1,没有地址\n#EXTINF:-1,东方卫视\nhttp://a.com/dongfang.m3u8";
+
+    const TXT: &str = "央视频道,#genre#\r\nCCTV1,http://a.com/1.m3u8#http://b.com/1.m3u8\r\n\
+                       卫视频道,#genre#\r\n湖南卫视,http://a.com/hunan.m3u8$高清\r\n无效的行\r\n深圳卫视,http://a.com/sz.m3u8";
+
+    fn parse_in_chunks(bytes: &[u8], size: usize) -> ParsedContent {
+        let mut parser = StreamingParser::new("http://a.com/list", None);
+        for chunk in bytes.chunks(size) {
+            parser.feed(chunk);
+        }
+        parser.finish().unwrap()
+    }
+
+    fn streamed_playlist(bytes: &[u8], size: usize) -> Playlist {
+        match parse_in_chunks(bytes, size) {
+            ParsedContent::Playlist(playlist) => *playlist,
+            ParsedContent::Document(_) => panic!("应该是播放列表"),
+        }
+    }
+
+    /// 频道、元数据和问题报告的 JSON，用于比较两次解析的结果
+    fn summary(playlist: &Playlist) -> serde_json::Value {
+        let lines: Vec<usize> = playlist.report.issues.iter().map(|issue| issue.line).collect();
+        serde_json::json!({ "meta": playlist.meta, "channels": playlist.channels, "issues": lines })
+    }
+
+    /// 在任意位置拆分（行中间、CRLF 之间、多字节字符和 BOM 中间）的结果与整体解析相同
+    fn assert_chunking_is_transparent(bytes: &[u8], text: &str, encoding: &str) {
+        let expected = summary(&parse_playlist_content(text, "http://a.com/list").unwrap());
+        for size in [1, 2, 3, 4, 5, 7, 11, 64, bytes.len()] {
+            let playlist = streamed_playlist(bytes, size);
+            assert_eq!(summary(&playlist), expected, "块大小 {}", size);
+            assert_eq!(playlist.encoding.as_deref(), Some(encoding), "块大小 {}", size);
+        }
+    }
+
+    #[test]
+    fn streaming_utf8_m3u_matches_whole_parse() {
+        assert_chunking_is_transparent(M3U.as_bytes(), M3U, "UTF-8");
+        let playlist = streamed_playlist(M3U.as_bytes(), 3);
+        let names: Vec<&str> = playlist.channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["CCTV-1 综合", "湖南卫视", "东方卫视"]);
+        assert_eq!(playlist.channels[0].http.user_agent.as_deref(), Some("VLC"));
+    }
+
+    #[test]
+    fn streaming_gbk_txt_matches_whole_parse() {
+        let (bytes, _, _) = encoding_rs::GBK.encode(TXT);
+        assert_chunking_is_transparent(&bytes, TXT, "GBK");
+        let playlist = streamed_playlist(&bytes, 1);
+        assert_eq!(playlist.channels.len(), 3);
+        assert_eq!(playlist.channels[0].backup_urls, ["http://b.com/1.m3u8"]);
+        assert_eq!(playlist.channels[1].group.as_deref(), Some("卫视频道"));
+    }
+
+    #[test]
+    fn streaming_utf16_matches_whole_parse() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(TXT.encode_utf16().flat_map(u16::to_le_bytes));
+        assert_chunking_is_transparent(&bytes, TXT, "UTF-16LE");
+    }
+
+    #[test]
+    fn detects_playlist_format() {
+        assert_eq!(detect_format("\u{feff}  #EXTM3U\n"), PlaylistFormat::M3u);
+        assert_eq!(detect_format("#EXTINF:-1,A\nhttp://a/1"), PlaylistFormat::M3u);
+        assert_eq!(detect_format("央视,#genre#\n"), PlaylistFormat::Txt);
+        assert_eq!(detect_format("# 注释\nCCTV1,http://a/1"), PlaylistFormat::Txt);
+        assert_eq!(detect_format("http://a/live.m3u8"), PlaylistFormat::M3u);
+
+        // 格式在前 20 行内无法识别时按已有内容判断
+        let mut content = "# 注释\n".repeat(25);
+        content.push_str("CCTV1,http://a/1\n");
+        assert_eq!(summary(&streamed_playlist(content.as_bytes(), 7)), summary(&parse_playlist_content(&content, "").unwrap()));
+    }
+
+    #[test]
+    fn streaming_returns_json_configs_as_documents() {
+        for content in ["{\"lives\": [{\"name\": \"直播\", \"url\": \"http://a/live.txt\"}]}", "\u{feff}\n// 注释\r\n{\"lives\": []}\n"] {
+            for size in [1, 3, content.len()] {
+                let ParsedContent::Document(document) = parse_in_chunks(content.as_bytes(), size) else {
+                    panic!("应该原样返回 JSON 配置");
+                };
+                assert_eq!(document.trim(), content.trim_start_matches('\u{feff}').replace("\r\n", "\n").trim());
+            }
+        }
+    }
+}
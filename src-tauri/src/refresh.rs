@@ -0,0 +1,344 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, instrument, warn};
+
+use crate::channel_name::ChannelAliases;
+use crate::playlist::Playlist;
+use crate::{epg, load_source_content, AppState, HttpValidators, LoadTask, LoadedContent, Source, SourceKind};
+
+/// 后台任务检查订阅源是否到期的间隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// 刷新完成时发送给前端的事件
+pub const REFRESH_EVENT: &str = "source-refreshed";
+
+/// 刷新的触发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshTrigger {
+    /// 用户点击刷新
+    Manual,
+    /// 后台定时刷新
+    Scheduled,
+    /// 监视的本地文件发生变化
+    FileChanged,
+}
+
+/// 单个订阅源的刷新结果，同时作为 `source-refreshed` 事件的内容
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshResult {
+    pub source_id: String,
+    pub name: String,
+    pub success: bool,
+    /// 服务器返回 304，内容没有变化
+    pub not_modified: bool,
+    /// 刷新后的频道数（失败时为原有频道数）
+    pub channel_count: usize,
+    pub previous_channel_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix 时间戳（秒）
+    pub refreshed_at: i64,
+    pub trigger: RefreshTrigger,
+}
+
+impl RefreshResult {
+    fn new(source: &Source, previous_channel_count: usize, not_modified: bool, refreshed_at: i64, trigger: RefreshTrigger) -> RefreshResult {
+        RefreshResult {
+            source_id: source.id.clone(),
+            name: source.name.clone(),
+            success: source.last_error.is_none(),
+            not_modified,
+            channel_count: source.channels.len(),
+            previous_channel_count,
+            error: source.last_error.clone(),
+            refreshed_at,
+            trigger,
+        }
+    }
+
+    /// 频道有变化，节目单地址可能也有变化
+    fn needs_epg_sync(&self) -> bool {
+        self.success && !self.not_modified
+    }
+}
+
+/// 重新加载的结果：`Ok(None)` 表示服务器返回 304，内容没有变化
+type Loaded = Result<Option<(Playlist, Option<HttpValidators>)>, String>;
+
+/// 正在刷新的订阅源，结束时（包括出错和取消）自动移除
+struct RefreshGuard<'a> {
+    state: &'a AppState,
+    source_id: String,
+}
+
+impl<'a> RefreshGuard<'a> {
+    fn acquire(state: &'a AppState, source_id: &str) -> Option<RefreshGuard<'a>> {
+        if !state.refreshing.lock().unwrap().insert(source_id.to_string()) {
+            return None;
+        }
+        Some(RefreshGuard {
+            state,
+            source_id: source_id.to_string(),
+        })
+    }
+}
+
+impl Drop for RefreshGuard<'_> {
+    fn drop(&mut self) {
+        self.state.refreshing.lock().unwrap().remove(&self.source_id);
+    }
+}
+
+/// 重新下载并解析订阅源：成功时替换频道，失败时保留原有频道并记录错误，完成后发送 `source-refreshed` 事件
+///
+/// 只有找不到订阅源、正在刷新或被取消时返回 `Err`，加载失败通过 `RefreshResult::error` 返回。
+#[instrument(skip(app, task))]
+pub async fn refresh(app: &AppHandle, source_id: &str, task: &LoadTask, trigger: RefreshTrigger) -> Result<RefreshResult, String> {
+    let state = app.state::<AppState>();
+    let (name, kind, previous_channel_count, validators, http_settings) = {
+        let sources = state.sources.lock().unwrap();
+        let source = sources
+            .iter()
+            .find(|s| s.id == source_id)
+            .ok_or_else(|| format!("未找到订阅源: {}", source_id))?;
+        (
+            source.name.clone(),
+            source.kind.clone(),
+            source.channels.len(),
+            source.http_cache.clone(),
+            source.http_settings.clone(),
+        )
+    };
+
+    let Some(_guard) = RefreshGuard::acquire(&state, source_id) else {
+        warn!("订阅源 '{}' 正在刷新", name);
+        return Err(format!("订阅源 '{}' 正在刷新", name));
+    };
+
+    info!("刷新订阅源 '{}': {}", name, kind.describe());
+    let loaded = load_source_content(&kind, &http_settings, validators.as_ref(), task).await.and_then(|loaded| match loaded {
+        LoadedContent::Playlist(playlist, validators) => Ok(Some((*playlist, validators))),
+        LoadedContent::TvboxConfig { .. } => Err("订阅地址返回的是 TVBox 配置，请通过添加订阅源导入".to_string()),
+        LoadedContent::NotModified => Ok(None),
+    });
+
+    if task.is_cancelled() {
+        warn!("订阅源 '{}' 刷新已取消", name);
+        return Err("已取消加载".to_string());
+    }
+
+    let refreshed_at = chrono::Utc::now().timestamp();
+    let not_modified = matches!(loaded, Ok(None));
+    let result = {
+        let mut sources = state.sources.lock().unwrap();
+        let source = sources
+            .iter_mut()
+            .find(|s| s.id == source_id)
+            .ok_or_else(|| format!("订阅源 '{}' 在刷新期间已被删除", name))?;
+
+        apply_loaded(source, loaded, &state.aliases, refreshed_at);
+        source.file_missing = match &kind {
+            SourceKind::LocalFile { path } => !Path::new(path).exists(),
+            _ => false,
+        };
+        RefreshResult::new(source, previous_channel_count, not_modified, refreshed_at, trigger)
+    };
+
+    state.save_sources()?;
+    if result.needs_epg_sync() {
+        epg::spawn_sync(app);
+    }
+
+    if let Err(e) = app.emit(REFRESH_EVENT, &result) {
+        warn!("发送刷新事件失败: {}", e);
+    }
+    Ok(result)
+}
+
+/// 把重新加载的结果应用到订阅源
+///
+/// 成功时替换频道，并重新应用 TVBox 直播条目的 EPG 和台标模板；304 时只更新刷新时间；失败时保留原有频道并记录错误。
+fn apply_loaded(source: &mut Source, loaded: Loaded, aliases: &ChannelAliases, refreshed_at: i64) {
+    let previous_channel_count = source.channels.len();
+    match loaded {
+        Ok(None) => {
+            info!("订阅源 '{}' 没有变化，保留 {} 个频道", source.name, previous_channel_count);
+            source.last_refreshed = Some(refreshed_at);
+            source.last_error = None;
+        }
+        Ok(Some((mut playlist, validators))) => {
+            if let Some(entry) = &source.live_entry {
+                playlist.apply_live_entry(entry, aliases);
+            }
+            if source.merge_duplicates {
+                playlist.merge_duplicates();
+            }
+            info!("订阅源 '{}' 刷新成功，频道数 {} -> {}", source.name, previous_channel_count, playlist.channels.len());
+            source.channels = playlist.channels;
+            source.meta = playlist.meta;
+            source.encoding = playlist.encoding;
+            source.channel_count = source.channels.len();
+            source.last_refreshed = Some(refreshed_at);
+            source.last_error = None;
+            source.http_cache = validators;
+        }
+        Err(e) => {
+            warn!("订阅源 '{}' 刷新失败，保留原有频道: {}", source.name, e);
+            source.last_error = Some(e);
+        }
+    }
+}
+
+/// 后台定时刷新：每分钟检查一次，刷新间隔已到期的订阅源依次刷新
+///
+/// 失败的订阅源同样按间隔重试，不会每分钟重复请求。
+pub async fn run_scheduler(app: AppHandle) {
+    info!("订阅源定时刷新任务已启动");
+    let mut attempts: HashMap<String, i64> = HashMap::new();
+    let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        ticker.tick().await;
+
+        let now = chrono::Utc::now().timestamp();
+        let due: Vec<String> = {
+            let state = app.state::<AppState>();
+            let sources = state.sources.lock().unwrap();
+            attempts.retain(|id, _| sources.iter().any(|s| &s.id == id));
+            sources
+                .iter()
+                .filter_map(|source| {
+                    let minutes = source.refresh_interval.filter(|m| *m > 0)?;
+                    let last = source.last_refreshed.max(attempts.get(&source.id).copied()).unwrap_or(0);
+                    (now - last >= i64::from(minutes) * 60).then(|| source.id.clone())
+                })
+                .collect()
+        };
+
+        if due.is_empty() {
+            continue;
+        }
+        debug!("{} 个订阅源到期需要刷新", due.len());
+
+        for source_id in due {
+            attempts.insert(source_id.clone(), now);
+            let state = app.state::<AppState>();
+            let task = LoadTask::new(&app, &state.load_tasks, None);
+            if let Err(e) = refresh(&app, &source_id, &task, RefreshTrigger::Scheduled).await {
+                warn!("定时刷新订阅源 {} 失败: {}", source_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist::parse_playlist_content;
+    use crate::tvbox::LiveEntry;
+
+    fn source(channels: &[&str]) -> Source {
+        let channels: Vec<serde_json::Value> = channels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name, "url": format!("http://a.com/{}.m3u8", name) }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "id": "source-1",
+            "name": "测试",
+            "kind": { "type": "remote", "url": "http://a.com/live.txt" },
+            "channels": channels,
+            "last_refreshed": 100,
+            "http_cache": { "etag": "\"v1\"" },
+        }))
+        .unwrap()
+    }
+
+    fn aliases() -> (tempfile::TempDir, ChannelAliases) {
+        let dir = tempfile::tempdir().unwrap();
+        let aliases = ChannelAliases::load(dir.path());
+        (dir, aliases)
+    }
+
+    fn names(source: &Source) -> Vec<&str> {
+        source.channels.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn failure_keeps_previous_channels() {
+        let (_dir, aliases) = aliases();
+        let mut source = source(&["CCTV1", "CCTV2"]);
+        apply_loaded(&mut source, Err("下载失败: 连接超时".to_string()), &aliases, 200);
+
+        assert_eq!(names(&source), ["CCTV1", "CCTV2"]);
+        assert_eq!(source.last_refreshed, Some(100));
+        assert_eq!(source.last_error.as_deref(), Some("下载失败: 连接超时"));
+        assert!(source.http_cache.is_some());
+
+        let result = RefreshResult::new(&source, 2, false, 200, RefreshTrigger::Scheduled);
+        assert!(!result.success);
+        assert_eq!(result.channel_count, 2);
+        assert!(!result.needs_epg_sync());
+    }
+
+    #[test]
+    fn success_replaces_channels_and_clears_error() {
+        let (_dir, aliases) = aliases();
+        let mut source = source(&["CCTV1"]);
+        source.last_error = Some("上次失败".to_string());
+        let playlist = parse_playlist_content("CCTV1,http://b.com/1.m3u8\nCCTV2,http://b.com/2.m3u8\n", "").unwrap();
+        apply_loaded(&mut source, Ok(Some((playlist, None))), &aliases, 200);
+
+        assert_eq!(names(&source), ["CCTV1", "CCTV2"]);
+        assert_eq!(source.channel_count, 2);
+        assert_eq!(source.last_refreshed, Some(200));
+        assert_eq!(source.last_error, None);
+        assert_eq!(source.http_cache, None);
+        assert!(RefreshResult::new(&source, 1, false, 200, RefreshTrigger::Manual).needs_epg_sync());
+    }
+
+    #[test]
+    fn not_modified_keeps_channels_and_updates_time() {
+        let (_dir, aliases) = aliases();
+        let mut source = source(&["CCTV1", "CCTV2"]);
+        source.last_error = Some("上次失败".to_string());
+        apply_loaded(&mut source, Ok(None), &aliases, 200);
+
+        assert_eq!(names(&source), ["CCTV1", "CCTV2"]);
+        assert_eq!(source.last_refreshed, Some(200));
+        assert_eq!(source.last_error, None);
+        assert_eq!(source.http_cache.as_ref().and_then(|v| v.etag.as_deref()), Some("\"v1\""));
+
+        let result = RefreshResult::new(&source, 2, true, 200, RefreshTrigger::Scheduled);
+        assert!(result.success);
+        assert!(result.not_modified);
+        assert!(!result.needs_epg_sync());
+    }
+
+    #[test]
+    fn reload_reapplies_tvbox_entry() {
+        let (_dir, aliases) = aliases();
+        let mut source = source(&["CCTV1"]);
+        source.live_entry = Some(LiveEntry {
+            name: "直播".to_string(),
+            url: "http://a.com/live.txt".to_string(),
+            user_agent: None,
+            epg: Some("http://epg.example/?ch={name}&date={date}".to_string()),
+            logo: Some("https://logo.example/{name}.png".to_string()),
+        });
+        let playlist = parse_playlist_content("CCTV-1 综合,http://b.com/1.m3u8\n", "").unwrap();
+        apply_loaded(&mut source, Ok(Some((playlist, None))), &aliases, 200);
+
+        assert_eq!(source.meta.epg_urls, ["http://epg.example/?ch={name}&date={date}"]);
+        assert_eq!(source.meta.logo_template.as_deref(), Some("https://logo.example/{name}.png"));
+        assert_eq!(source.channels[0].logo.as_deref(), Some("https://logo.example/CCTV1.png"));
+
+        // 保存后重新读取，条目仍然保留
+        let saved: Source = serde_json::from_str(&serde_json::to_string(&source).unwrap()).unwrap();
+        assert_eq!(saved.live_entry.and_then(|entry| entry.epg), source.meta.epg_urls.first().cloned());
+    }
+}
@@ -0,0 +1,331 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::channel_name::ChannelAliases;
+use crate::playlist::Playlist;
+use crate::ImportFailure;
+
+/// TVBox / DIYP 配置中的一个直播条目，保存在导入的订阅源上，每次重新加载后再次应用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// EPG 接口模板，例如 `http://epg.example/?ch={name}&date={date}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epg: Option<String>,
+    /// 台标模板，例如 `https://logo.example/{name}.png`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+}
+
+/// 去掉配置中常见的 `//` 注释行
+fn strip_comment_lines(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_config(content: &str) -> Option<Value> {
+    let stripped = strip_comment_lines(content.trim_start_matches('\u{feff}'));
+    let stripped = stripped.trim_start();
+    if !stripped.starts_with('{') {
+        return None;
+    }
+    serde_json::from_str(stripped).ok()
+}
+
+/// 内容是否为带有 `lives` 的 TVBox JSON 配置
+pub fn is_tvbox_config(content: &str) -> bool {
+    parse_config(content).is_some_and(|config| config.get("lives").is_some_and(Value::is_array))
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// `ua` 字段或 `header` 中的 User-Agent
+fn user_agent_field(live: &Value) -> Option<String> {
+    string_field(live, "ua").or_else(|| {
+        live.get("header")?
+            .as_object()?
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+            .and_then(|(_, value)| value.as_str())
+            .map(str::to_string)
+    })
+}
+
+/// 旧版配置的 `proxy://do=live&type=txt&ext=<base64 或 URL>`
+fn decode_proxy_url(url: &str) -> Option<String> {
+    let query = url.strip_prefix("proxy://")?;
+    let ext = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("ext="))?;
+    let ext = urlencoding::decode(ext).map(|s| s.into_owned()).unwrap_or_else(|_| ext.to_string());
+
+    if ext.contains("://") {
+        return Some(ext);
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(ext.trim())
+        .or_else(|_| base64::engine::general_purpose::URL_SAFE.decode(ext.trim()))
+        .ok()?;
+    String::from_utf8(decoded).ok().map(|s| s.trim().to_string())
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// 相对地址按配置文件所在位置解析
+///
+/// 网络配置中的地址一律按配置地址解析，只能是 http(s) 地址；只有本地配置文件可以引用本地文件。
+fn resolve_url(base: Option<&str>, url: &str) -> Result<String, String> {
+    if let Some(base) = base.filter(|base| is_http_url(base)) {
+        let base = reqwest::Url::parse(base).map_err(|e| format!("配置地址无效: {}", e))?;
+        let resolved = base.join(url).map_err(|e| format!("地址无效: {}", e))?;
+        if !matches!(resolved.scheme(), "http" | "https") {
+            return Err("网络配置中只能使用 http(s) 地址".to_string());
+        }
+        return Ok(resolved.into());
+    }
+    if is_http_url(url) {
+        return Ok(url.to_string());
+    }
+
+    let Some(base) = base else {
+        return Err("粘贴的配置中只能使用 http(s) 地址".to_string());
+    };
+    if url.contains("://") {
+        return Err(format!("不支持的地址: {}", url));
+    }
+    let path = Path::new(url);
+    if path.is_absolute() {
+        return Ok(url.to_string());
+    }
+    Ok(Path::new(base)
+        .parent()
+        .map(|dir| dir.join(url.trim_start_matches("./")))
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// 解析 TVBox 配置中的 `lives`，`base` 为配置文件自身的地址或路径（用于解析相对地址）
+///
+/// 每个条目单独返回成功或失败，无法使用的条目不会影响其他条目。
+pub fn parse_live_entries(content: &str, base: Option<&str>) -> Result<Vec<Result<LiveEntry, ImportFailure>>, String> {
+    let config = parse_config(content).ok_or("不是有效的 TVBox JSON 配置")?;
+    let lives = config
+        .get("lives")
+        .and_then(Value::as_array)
+        .ok_or("配置中没有 lives 字段")?;
+
+    let mut entries = Vec::new();
+
+    for (index, live) in lives.iter().enumerate() {
+        let name = string_field(live, "name")
+            .or_else(|| string_field(live, "group"))
+            .unwrap_or_else(|| format!("直播 {}", index + 1));
+
+        if let Some(url) = string_field(live, "url") {
+            // type 3 为爬虫（spider）类型，需要执行 jar/js，无法直接导入
+            if live.get("type").and_then(Value::as_i64) == Some(3) {
+                entries.push(Err(ImportFailure {
+                    name,
+                    url,
+                    error: "不支持爬虫类型（type=3）的直播".to_string(),
+                }));
+                continue;
+            }
+
+            entries.push(match resolve_url(base, &url) {
+                Ok(resolved) => Ok(LiveEntry {
+                    url: resolved,
+                    user_agent: user_agent_field(live),
+                    epg: string_field(live, "epg"),
+                    logo: string_field(live, "logo"),
+                    name,
+                }),
+                Err(error) => Err(ImportFailure { name, url, error }),
+            });
+            continue;
+        }
+
+        // 旧版格式：{"group": "redirect", "channels": [{"name": "...", "urls": ["proxy://..."]}]}
+        let Some(channels) = live.get("channels").and_then(Value::as_array) else {
+            entries.push(Err(ImportFailure {
+                name,
+                url: String::new(),
+                error: "直播条目缺少 url".to_string(),
+            }));
+            continue;
+        };
+
+        for channel in channels {
+            let channel_name = string_field(channel, "name").unwrap_or_else(|| name.clone());
+            let urls = channel.get("urls").and_then(Value::as_array).cloned().unwrap_or_default();
+
+            for raw in urls.iter().filter_map(Value::as_str) {
+                let decoded = if raw.starts_with("proxy://") {
+                    decode_proxy_url(raw).ok_or_else(|| "无法解析 proxy 地址".to_string())
+                } else {
+                    Ok(raw.to_string())
+                };
+
+                match decoded.and_then(|url| resolve_url(base, &url)) {
+                    Ok(url) => entries.push(Ok(LiveEntry {
+                        name: channel_name.clone(),
+                        url,
+                        user_agent: user_agent_field(live),
+                        epg: string_field(live, "epg"),
+                        logo: string_field(live, "logo"),
+                    })),
+                    Err(error) => entries.push(Err(ImportFailure {
+                        name: channel_name.clone(),
+                        url: raw.to_string(),
+                        error,
+                    })),
+                }
+            }
+        }
+    }
+
+    info!("TVBox 配置包含 {} 个直播条目", entries.len());
+    Ok(entries)
+}
+
+impl Playlist {
+    /// 应用直播条目上的 UA、EPG 和台标模板，台标按规范化的频道名（应用别名后）生成
+    pub fn apply_live_entry(&mut self, entry: &LiveEntry, aliases: &ChannelAliases) {
+        if let Some(user_agent) = &entry.user_agent {
+            self.meta.user_agent = Some(user_agent.clone());
+        }
+        if let Some(epg) = &entry.epg {
+            if !self.meta.epg_urls.contains(epg) {
+                self.meta.epg_urls.push(epg.clone());
+            }
+        }
+        if let Some(logo) = &entry.logo {
+            self.meta.logo_template = Some(logo.clone());
+        }
+
+        let mut filled = 0;
+        for channel in &mut self.channels {
+            channel.inherit_defaults(&self.meta);
+            if channel.logo.is_none() {
+                if let Some(template) = &self.meta.logo_template {
+                    channel.logo = Some(template.replace("{name}", &urlencoding::encode(&aliases.canonical(&channel.name))));
+                    filled += 1;
+                }
+            }
+        }
+        debug!("按台标模板补充了 {} 个频道的台标", filled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://host.example/tv/cfg.json";
+
+    fn parse(content: &str, base: Option<&str>) -> Vec<Result<LiveEntry, ImportFailure>> {
+        parse_live_entries(content, base).unwrap()
+    }
+
+    #[test]
+    fn detects_configs_with_comment_lines() {
+        assert!(is_tvbox_config("\u{feff}// 注释\n{\"lives\": []}"));
+        assert!(!is_tvbox_config("{\"sites\": []}"));
+        assert!(!is_tvbox_config("#EXTM3U\n"));
+        assert!(parse_live_entries("{\"sites\": []}", None).is_err());
+    }
+
+    #[test]
+    fn rejects_spider_entries() {
+        let entries = parse(r#"{"lives": [{"name": "爬虫", "type": 3, "url": "csp_Live"}, {"name": "直播", "type": 0, "url": "live.txt"}]}"#, Some(BASE));
+        let failure = entries[0].as_ref().unwrap_err();
+        assert_eq!(failure.name, "爬虫");
+        assert!(failure.error.contains("type=3"));
+        assert_eq!(entries[1].as_ref().unwrap().url, "https://host.example/tv/live.txt");
+    }
+
+    #[test]
+    fn extracts_user_agent_epg_and_logo() {
+        let content = r#"{"lives": [
+            {"name": "A", "url": "http://a.com/a.m3u", "ua": "okhttp/3.15", "epg": "http://epg.com/?ch={name}&date={date}", "logo": "http://logo.com/{name}.png"},
+            {"name": "B", "url": "http://a.com/b.m3u", "header": {"user-agent": "Kodi", "Referer": "http://r/"}},
+            {"url": "http://a.com/c.m3u"}
+        ]}"#;
+        let entries: Vec<LiveEntry> = parse(content, Some(BASE)).into_iter().map(Result::unwrap).collect();
+        assert_eq!(entries[0].user_agent.as_deref(), Some("okhttp/3.15"));
+        assert_eq!(entries[0].epg.as_deref(), Some("http://epg.com/?ch={name}&date={date}"));
+        assert_eq!(entries[0].logo.as_deref(), Some("http://logo.com/{name}.png"));
+        assert_eq!(entries[1].user_agent.as_deref(), Some("Kodi"));
+        assert_eq!(entries[1].epg, None);
+        assert_eq!(entries[2].name, "直播 3");
+        assert_eq!(entries[2].user_agent, None);
+    }
+
+    #[test]
+    fn decodes_legacy_proxy_channels() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode("http://a.com/live.txt");
+        let content = format!(
+            r#"{{"lives": [{{"group": "redirect", "channels": [{{"name": "旧版", "urls": ["proxy://do=live&type=txt&ext={}", "proxy://do=live&type=txt&ext=http%3A%2F%2Fb.com%2Flive.txt", "proxy://do=live&ext=!!!"]}}]}}]}}"#,
+            encoded
+        );
+        let entries = parse(&content, None);
+        let urls: Vec<&str> = entries[..2].iter().map(|e| e.as_ref().unwrap().url.as_str()).collect();
+        assert_eq!(urls, ["http://a.com/live.txt", "http://b.com/live.txt"]);
+        assert_eq!(entries[0].as_ref().unwrap().name, "旧版");
+        assert!(entries[2].is_err());
+
+        let missing = parse(r#"{"lives": [{"name": "空"}]}"#, None);
+        assert!(missing[0].is_err());
+    }
+
+    #[test]
+    fn resolves_urls_against_remote_config() {
+        let base = Some(BASE);
+        assert_eq!(resolve_url(base, "live.txt").unwrap(), "https://host.example/tv/live.txt");
+        assert_eq!(resolve_url(base, "./sub/live.txt").unwrap(), "https://host.example/tv/sub/live.txt");
+        assert_eq!(resolve_url(base, "/live/cctv.txt").unwrap(), "https://host.example/live/cctv.txt");
+        assert_eq!(resolve_url(base, "//cdn.example/a.m3u").unwrap(), "https://cdn.example/a.m3u");
+        assert_eq!(resolve_url(base, "http://other.example/a.m3u").unwrap(), "http://other.example/a.m3u");
+    }
+
+    #[test]
+    fn remote_and_pasted_configs_cannot_reference_local_files() {
+        assert!(resolve_url(Some(BASE), "file:///etc/passwd").is_err());
+        assert!(resolve_url(Some(BASE), "C:\\Windows\\win.ini").is_err());
+        assert!(resolve_url(None, "/etc/passwd").is_err());
+        assert!(resolve_url(None, "live.txt").is_err());
+        assert_eq!(resolve_url(None, "https://a.com/live.txt").unwrap(), "https://a.com/live.txt");
+
+        let entries = parse(r#"{"lives": [{"name": "本地", "url": "file:///etc/passwd"}]}"#, Some(BASE));
+        assert!(entries[0].is_err());
+    }
+
+    #[test]
+    fn resolves_paths_against_local_config() {
+        let base = Some("/home/user/tv/cfg.json");
+        assert_eq!(resolve_url(base, "live.txt").unwrap(), "/home/user/tv/live.txt");
+        assert_eq!(resolve_url(base, "./sub/live.txt").unwrap(), "/home/user/tv/sub/live.txt");
+        assert_eq!(resolve_url(base, "/srv/live.txt").unwrap(), "/srv/live.txt");
+        assert_eq!(resolve_url(base, "http://a.com/live.txt").unwrap(), "http://a.com/live.txt");
+        assert!(resolve_url(base, "rtmp://a.com/live").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use tracing::{debug, info, warn};
+
+use crate::diagnostics::{IssueKind, ParseReport};
+use crate::m3u::split_lines;
+use crate::playlist::Playlist;
+use crate::Channel;
+
+/// 解析 DIYP / TXT 格式的播放列表
+///
+/// ```text
+/// 央视频道,#genre#
+/// CCTV1,http://a.example/1.m3u8#http://b.example/1.m3u8
+/// CCTV2,http://c.example/2.m3u8$高清
+/// ```
+///
+/// 一行中用 `#` 分隔的多个地址会成为同一频道的备用地址，`$` 后面的线路说明会被去掉。
+pub fn parse_txt_content(content: &str) -> Result<Playlist, String> {
+    let mut parser = TxtParser::default();
+    for line in split_lines(content) {
+        parser.push_line(line);
+    }
+    parser.finish()
+}
+
+/// 逐行解析 TXT，一次性解析和流式解析共用
+#[derive(Debug, Default)]
+pub struct TxtParser {
+    channels: Vec<Channel>,
+    group: Option<String>,
+    /// 当前行号
+    line: usize,
+    report: ParseReport,
+}
+
+impl TxtParser {
+    /// 输入一行（已去掉换行符）
+    pub fn push_line(&mut self, line: &str) {
+        self.line += 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            return;
+        }
+
+        let Some((name, rest)) = line.split_once(',') else {
+            debug!("跳过无法识别的行: {}", line);
+            self.report.add(self.line, IssueKind::Skipped, format!("无法识别的行: {}", line));
+            return;
+        };
+        let name = name.trim();
+        let rest = rest.trim();
+
+        // 分组行
+        if rest.eq_ignore_ascii_case("#genre#") {
+            self.group = if name.is_empty() { None } else { Some(name.to_string()) };
+            return;
+        }
+
+        let mut urls = split_urls(rest).into_iter();
+        let Some(url) = urls.next() else {
+            debug!("频道 {} 没有有效地址", name);
+            self.report.add(self.line, IssueKind::Skipped, format!("频道 {} 没有有效的播放地址", name));
+            return;
+        };
+        if name.is_empty() {
+            self.report.add(self.line, IssueKind::MissingName, "频道没有名称");
+        }
+        self.report.check_duplicate_url(self.line, &url);
+
+        self.channels.push(Channel {
+            name: if name.is_empty() { "未命名频道".to_string() } else { name.to_string() },
+            url,
+            backup_urls: urls.collect(),
+            group: self.group.clone(),
+            ..Default::default()
+        });
+    }
+
+    /// 已解析的频道数
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn finish(mut self) -> Result<Playlist, String> {
+        self.report.finish(self.line);
+        if self.channels.is_empty() {
+            warn!("未找到有效的频道信息");
+            if self.report.is_empty() {
+                Err("未找到有效的频道信息".to_string())
+            } else {
+                Err(format!("未找到有效的频道信息（{}）", self.report.summary()))
+            }
+        } else {
+            if !self.report.is_empty() {
+                warn!("播放列表解析问题: {}", self.report.summary());
+            }
+            info!("成功解析 {} 个 TXT 频道", self.channels.len());
+            Ok(Playlist {
+                report: self.report,
+                ..Playlist::from_channels(self.channels)
+            })
+        }
+    }
+}
+
+/// 拆分一行中的多个地址：只有 `#` 后面紧跟 `scheme://` 时才视为分隔符，避免误拆 URL 片段
+fn split_urls(value: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut current = String::new();
+
+    for (i, part) in value.split('#').enumerate() {
+        if i > 0 && !is_url(part.trim_start()) {
+            current.push('#');
+            current.push_str(part);
+            continue;
+        }
+        if !current.is_empty() {
+            urls.push(std::mem::take(&mut current));
+        }
+        current.push_str(part);
+    }
+    if !current.is_empty() {
+        urls.push(current);
+    }
+
+    urls.into_iter()
+        .map(|url| strip_line_label(url.trim()).to_string())
+        .filter(|url| is_url(url))
+        .collect()
+}
+
+/// 去掉 `$线路名` 形式的后缀
+fn strip_line_label(url: &str) -> &str {
+    match url.rfind('$') {
+        Some(pos) if !url[pos + 1..].contains('/') => &url[..pos],
+        _ => url,
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    value
+        .split_once("://")
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genre_lines_set_group_for_following_channels() {
+        let content = "CCTV0,http://a.com/0.m3u8\n\
+                       央视频道,#genre#\n\
+                       CCTV1,http://a.com/1.m3u8\n\
+                       CCTV2,http://a.com/2.m3u8\n\
+                       卫视频道 , #GENRE#\n\
+                       湖南卫视,http://a.com/hunan.m3u8\n\
+                       ,#genre#\n\
+                       其他,http://a.com/other.m3u8\n";
+        let playlist = parse_txt_content(content).unwrap();
+        let groups: Vec<(&str, Option<&str>)> = playlist.channels.iter().map(|c| (c.name.as_str(), c.group.as_deref())).collect();
+        assert_eq!(
+            groups,
+            [
+                ("CCTV0", None),
+                ("CCTV1", Some("央视频道")),
+                ("CCTV2", Some("央视频道")),
+                ("湖南卫视", Some("卫视频道")),
+                ("其他", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_backup_urls_and_strips_line_labels() {
+        let playlist = parse_txt_content("CCTV1,http://a.com/1.m3u8$高清#http://b.com/1.m3u8#rtmp://c.com/live\n").unwrap();
+        let channel = &playlist.channels[0];
+        assert_eq!(channel.url, "http://a.com/1.m3u8");
+        assert_eq!(channel.backup_urls, ["http://b.com/1.m3u8", "rtmp://c.com/live"]);
+    }
+
+    #[test]
+    fn keeps_fragments_and_dollar_signs_inside_urls() {
+        let playlist = parse_txt_content("A,http://a.com/play#anchor\nB,http://a.com/$path/b.m3u8\n").unwrap();
+        assert_eq!(playlist.channels[0].url, "http://a.com/play#anchor");
+        assert!(playlist.channels[0].backup_urls.is_empty());
+        assert_eq!(playlist.channels[1].url, "http://a.com/$path/b.m3u8");
+    }
+
+    #[test]
+    fn reports_skipped_and_unnamed_lines() {
+        let content = "# 注释\n// 注释\n无效的行\nCCTV1,不是地址\n,http://a.com/1.m3u8\nCCTV2,http://a.com/1.m3u8\n";
+        let playlist = parse_txt_content(content).unwrap();
+        assert_eq!(playlist.channels.len(), 2);
+        assert_eq!(playlist.channels[0].name, "未命名频道");
+        assert_eq!(playlist.report.count(IssueKind::Skipped), 2);
+        assert_eq!(playlist.report.count(IssueKind::MissingName), 1);
+        assert_eq!(playlist.report.count(IssueKind::DuplicateUrl), 1);
+        let lines: Vec<usize> = playlist.report.issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, [3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn only_groups_is_an_error() {
+        assert!(parse_txt_content("央视频道,#genre#\n卫视频道,#genre#\n").is_err());
+    }
+}
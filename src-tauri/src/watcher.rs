@@ -0,0 +1,159 @@
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, error, info, warn};
+
+use crate::refresh::{self, RefreshTrigger};
+use crate::{AppState, LoadTask, Source, SourceKind};
+
+/// 文件变化后等待的时间，编辑器保存时的多次写入合并为一次重新加载
+const DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// 文件路径 -> 使用该文件的订阅源 ID
+type WatchedFiles = Arc<Mutex<HashMap<PathBuf, Vec<String>>>>;
+
+/// 监视本地文件订阅源，文件变化时重新加载
+///
+/// 监视文件所在的目录而不是文件本身：编辑器常用"写临时文件再重命名"的方式保存，
+/// 直接监视文件会在第一次保存后失效，也无法发现文件被删除后重新创建。
+pub struct FileWatcher {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+    files: WatchedFiles,
+    /// 已经在监视的目录
+    dirs: Mutex<HashSet<PathBuf>>,
+}
+
+impl FileWatcher {
+    /// 创建监视器；系统不支持或资源不足时只记录日志，本地文件仍可手动刷新
+    pub fn new(app: AppHandle) -> FileWatcher {
+        let files: WatchedFiles = Arc::new(Mutex::new(HashMap::new()));
+        let handler_files = files.clone();
+        let debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let changed: HashSet<String> = {
+                    let files = handler_files.lock().unwrap();
+                    events
+                        .iter()
+                        .filter_map(|event| files.get(&normalize(&event.path)))
+                        .flatten()
+                        .cloned()
+                        .collect()
+                };
+                for source_id in changed {
+                    tauri::async_runtime::spawn(reload(app.clone(), source_id));
+                }
+            }
+            Err(e) => warn!("文件监视出错: {}", e),
+        });
+
+        let debouncer = match debouncer {
+            Ok(debouncer) => Some(debouncer),
+            Err(e) => {
+                error!("无法创建文件监视器: {}", e);
+                None
+            }
+        };
+
+        FileWatcher {
+            debouncer: Mutex::new(debouncer),
+            files,
+            dirs: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 按订阅源列表更新监视的文件：开启了监视的本地文件订阅源加入，其余移除
+    pub fn sync(&self, sources: &[Source]) {
+        let mut files: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        for source in sources.iter().filter(|s| s.watch_file) {
+            if let SourceKind::LocalFile { path } = &source.kind {
+                files.entry(normalize(Path::new(path))).or_default().push(source.id.clone());
+            }
+        }
+
+        let wanted: HashSet<PathBuf> = files
+            .keys()
+            .filter_map(|path| path.parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+
+        let mut debouncer = self.debouncer.lock().unwrap();
+        let mut dirs = self.dirs.lock().unwrap();
+        if let Some(debouncer) = debouncer.as_mut() {
+            for dir in dirs.difference(&wanted) {
+                debug!("停止监视目录: {:?}", dir);
+                if let Err(e) = debouncer.watcher().unwatch(dir) {
+                    debug!("停止监视目录 {:?} 失败: {}", dir, e);
+                }
+            }
+            for dir in wanted.difference(&dirs) {
+                match debouncer.watcher().watch(dir, RecursiveMode::NonRecursive) {
+                    Ok(()) => info!("开始监视目录: {:?}", dir),
+                    Err(e) => warn!("无法监视目录 {:?}: {}", dir, e),
+                }
+            }
+        }
+        *dirs = wanted;
+        *self.files.lock().unwrap() = files;
+    }
+}
+
+/// 比较路径前先规范化：解析目录中的符号链接（macOS 上 `/var` 实际是 `/private/var`，
+/// 监视器报告的是真实路径）；文件本身可能已被删除，所以只规范化所在目录
+fn normalize(path: &Path) -> PathBuf {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return path.to_path_buf();
+    };
+    match dir.canonicalize() {
+        Ok(dir) => dir.join(file_name),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// 文件变化后重新加载订阅源（文件被删除时保留原有频道并标记错误）
+async fn reload(app: AppHandle, source_id: String) {
+    let state = app.state::<AppState>();
+    let task = LoadTask::new(&app, &state.load_tasks, None);
+    match refresh::refresh(&app, &source_id, &task, RefreshTrigger::FileChanged).await {
+        Ok(result) if result.success => info!("本地文件已变化，订阅源 '{}' 重新加载，{} 个频道", result.name, result.channel_count),
+        Ok(result) => warn!("本地文件已变化，订阅源 '{}' 重新加载失败: {}", result.name, result.error.unwrap_or_default()),
+        Err(e) => debug!("跳过重新加载订阅源 {}: {}", source_id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_resolves_directory_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let real = temp.path().join("real");
+        std::fs::create_dir(&real).unwrap();
+        std::fs::write(real.join("list.m3u"), "#EXTM3U").unwrap();
+
+        let canonical = real.canonicalize().unwrap().join("list.m3u");
+        assert_eq!(normalize(&real.join("list.m3u")), canonical);
+        assert_eq!(normalize(&real.join(".").join("list.m3u")), canonical);
+        // 文件已被删除时仍按目录规范化
+        assert_eq!(normalize(&real.join("deleted.m3u")), real.canonicalize().unwrap().join("deleted.m3u"));
+
+        #[cfg(unix)]
+        {
+            let link = temp.path().join("link");
+            std::os::unix::fs::symlink(&real, &link).unwrap();
+            assert_eq!(normalize(&link.join("list.m3u")), canonical);
+        }
+    }
+
+    #[test]
+    fn normalize_keeps_unresolvable_paths() {
+        let path = Path::new("/nonexistent-dir/list.m3u");
+        assert_eq!(normalize(path), path);
+        assert_eq!(normalize(Path::new("list.m3u")), Path::new("list.m3u"));
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::NaiveDateTime;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::BufRead;
+use tracing::{debug, info};
+
+use crate::epg::{EpgChannel, Guide, Programme};
+
+/// 需要读取文本的子元素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    DisplayName,
+    Title,
+    SubTitle,
+    Desc,
+    Category,
+    EpisodeNum,
+}
+
+impl Field {
+    fn from_tag(tag: &[u8]) -> Option<Field> {
+        match tag {
+            b"display-name" => Some(Field::DisplayName),
+            b"title" => Some(Field::Title),
+            b"sub-title" => Some(Field::SubTitle),
+            b"desc" => Some(Field::Desc),
+            b"category" => Some(Field::Category),
+            b"episode-num" => Some(Field::EpisodeNum),
+            _ => None,
+        }
+    }
+}
+
+/// 正在解析的节目，`stop` 可以省略，解析完成后用下一个节目的开始时间补上
+struct PendingProgramme {
+    channel: String,
+    start: i64,
+    stop: Option<i64>,
+    programme: Programme,
+    /// 已经取到 `system="onscreen"` 的集数，不再被其他格式覆盖
+    onscreen_episode: bool,
+}
+
+/// 流式解析 XMLTV 节目单（`<channel>` 和 `<programme>`），不会把整个文件读入内存
+///
+/// 时间无法解析或没有 `channel` 属性的节目会被跳过。
+pub fn parse<R: BufRead>(input: R) -> Result<Guide, String> {
+    // 不裁剪文本事件：实体引用会把文本拆开（`新闻 &amp; 天气`），字段结束时再整体裁剪
+    let mut reader = Reader::from_reader(input);
+
+    let mut channels: Vec<EpgChannel> = Vec::new();
+    let mut pending: Vec<PendingProgramme> = Vec::new();
+    let mut channel: Option<EpgChannel> = None;
+    let mut programme: Option<PendingProgramme> = None;
+    let mut field: Option<(Field, bool)> = None;
+    let mut text = String::new();
+    let mut skipped = 0usize;
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XMLTV 解析失败（位置 {}）: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"channel" => channel = Some(start_channel(&reader, &e)),
+                b"programme" => {
+                    programme = start_programme(&reader, &e);
+                    if programme.is_none() {
+                        skipped += 1;
+                    }
+                }
+                tag => {
+                    if let Some(f) = Field::from_tag(tag) {
+                        let onscreen = f == Field::EpisodeNum && attribute(&reader, &e, b"system").as_deref() == Some("onscreen");
+                        field = Some((f, onscreen));
+                        text.clear();
+                    }
+                }
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"icon" => {
+                let src = attribute(&reader, &e, b"src").filter(|s| !s.is_empty());
+                if let Some(p) = programme.as_mut() {
+                    p.programme.icon = p.programme.icon.take().or(src);
+                } else if let Some(c) = channel.as_mut() {
+                    c.icon = c.icon.take().or(src);
+                }
+            }
+            Event::Text(t) if field.is_some() => {
+                text.push_str(&t.xml_content().map_err(|e| format!("XMLTV 编码错误: {}", e))?);
+            }
+            Event::CData(t) if field.is_some() => {
+                text.push_str(&t.decode().map_err(|e| format!("XMLTV 编码错误: {}", e))?);
+            }
+            Event::GeneralRef(r) if field.is_some() => {
+                if let Ok(Some(c)) = r.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(entity) = r.decode().ok().and_then(|name| resolve_predefined_entity(&name)) {
+                    text.push_str(entity);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"channel" => {
+                    if let Some(c) = channel.take().filter(|c| !c.id.is_empty()) {
+                        channels.push(c);
+                    }
+                }
+                b"programme" => pending.extend(programme.take()),
+                tag => {
+                    if let Some((f, onscreen)) = field.filter(|(f, _)| Field::from_tag(tag) == Some(*f)) {
+                        field = None;
+                        let value = text.trim().to_string();
+                        if !value.is_empty() {
+                            apply_field(f, onscreen, value, channel.as_mut(), programme.as_mut());
+                        }
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let guide = Guide::new(channels, finish_programmes(pending));
+    info!(
+        "XMLTV 解析完成: {} 个频道，{} 个节目，跳过 {} 个无效节目",
+        guide.channels.len(),
+        guide.programme_count(),
+        skipped
+    );
+    Ok(guide)
+}
+
+fn attribute<R>(reader: &Reader<R>, element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| a.decode_and_unescape_value(reader.decoder()).ok())
+        .map(|v| v.trim().to_string())
+}
+
+fn start_channel<R>(reader: &Reader<R>, element: &BytesStart) -> EpgChannel {
+    EpgChannel {
+        id: attribute(reader, element, b"id").unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+fn start_programme<R>(reader: &Reader<R>, element: &BytesStart) -> Option<PendingProgramme> {
+    let channel = attribute(reader, element, b"channel").filter(|c| !c.is_empty())?;
+    let start = attribute(reader, element, b"start").and_then(|s| parse_time(&s));
+    let Some(start) = start else {
+        debug!("跳过开始时间无效的节目，频道: {}", channel);
+        return None;
+    };
+    let stop = attribute(reader, element, b"stop").and_then(|s| parse_time(&s));
+    Some(PendingProgramme {
+        channel,
+        start,
+        stop,
+        programme: Programme::default(),
+        onscreen_episode: false,
+    })
+}
+
+/// 同一字段出现多次时（多语言），标题、副标题和简介取第一个，分类全部保留
+fn apply_field(field: Field, onscreen: bool, value: String, channel: Option<&mut EpgChannel>, programme: Option<&mut PendingProgramme>) {
+    if let Some(p) = programme {
+        let target = &mut p.programme;
+        match field {
+            Field::Title if target.title.is_empty() => target.title = value,
+            Field::SubTitle if target.sub_title.is_none() => target.sub_title = Some(value),
+            Field::Desc if target.desc.is_none() => target.desc = Some(value),
+            Field::Category if !target.categories.contains(&value) => target.categories.push(value),
+            Field::EpisodeNum if !p.onscreen_episode => {
+                target.episode = Some(value);
+                p.onscreen_episode = onscreen;
+            }
+            _ => {}
+        }
+    } else if let Some(c) = channel {
+        if field == Field::DisplayName && !c.display_names.contains(&value) {
+            c.display_names.push(value);
+        }
+    }
+}
+
+/// 按频道分组并按开始时间排序；没有结束时间的节目以下一个节目的开始时间结束，最后一个直接丢弃
+fn finish_programmes(pending: Vec<PendingProgramme>) -> HashMap<String, Vec<Programme>> {
+    let mut grouped: HashMap<String, Vec<PendingProgramme>> = HashMap::new();
+    for p in pending {
+        grouped.entry(p.channel.clone()).or_default().push(p);
+    }
+
+    grouped
+        .into_iter()
+        .map(|(channel, mut list)| {
+            list.sort_by_key(|p| p.start);
+            list.dedup_by_key(|p| p.start);
+            let next_starts: Vec<Option<i64>> = list.iter().skip(1).map(|p| Some(p.start)).chain([None]).collect();
+            let programmes = list
+                .into_iter()
+                .zip(next_starts)
+                .filter_map(|(p, next_start)| {
+                    let stop = p.stop.or(next_start).filter(|stop| *stop > p.start)?;
+                    Some(Programme {
+                        start: p.start,
+                        stop,
+                        ..p.programme
+                    })
+                })
+                .collect();
+            (channel, programmes)
+        })
+        .collect()
+}
+
+/// 解析 XMLTV 时间（`20261018203000 +0800`），返回 Unix 时间戳（秒）
+///
+/// 时分秒可以省略；没有时区时按 UTC 处理（XMLTV 规范的约定），时区也可以写作 `+08:00` 或 `Z`。
+pub fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (digits, offset) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => (&value[..i], value[i..].trim()),
+        None => (value, ""),
+    };
+    if digits.len() < 8 {
+        return None;
+    }
+    let padded = format!("{:0<14}", &digits[..digits.len().min(14)]);
+    let local = NaiveDateTime::parse_from_str(&padded, "%Y%m%d%H%M%S").ok()?;
+    Some(local.and_utc().timestamp() - parse_offset(offset)?)
+}
+
+/// 时区偏移（秒）：`+0800`、`-05:30`、`Z`、`UTC`、`GMT`
+fn parse_offset(offset: &str) -> Option<i64> {
+    if offset.is_empty() || offset.eq_ignore_ascii_case("z") || offset.eq_ignore_ascii_case("utc") || offset.eq_ignore_ascii_case("gmt") {
+        return Some(0);
+    }
+    let (sign, rest) = match offset.as_bytes()[0] {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let rest = rest.replace(':', "");
+    if rest.len() != 4 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = rest[..2].parse().ok()?;
+    let minutes: i64 = rest[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-18 12:30:00 UTC
+    const NOON_UTC: i64 = 1_792_326_600;
+
+    #[test]
+    fn parse_time_applies_offsets() {
+        assert_eq!(parse_time("20261018203000 +0800"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018203000 +08:00"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018203000+0800"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018073000 -0500"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018180000 +0530"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018123000 Z"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018123000 UTC"), Some(NOON_UTC));
+        // 没有时区时按 UTC
+        assert_eq!(parse_time(" 20261018123000 "), Some(NOON_UTC));
+    }
+
+    #[test]
+    fn parse_time_allows_missing_time_parts() {
+        assert_eq!(parse_time("20261018"), Some(NOON_UTC - 12 * 3600 - 1800));
+        assert_eq!(parse_time("202610181230 +0000"), Some(NOON_UTC));
+        assert_eq!(parse_time("20261018123000123 +0000"), Some(NOON_UTC));
+    }
+
+    #[test]
+    fn parse_time_rejects_invalid_values() {
+        assert_eq!(parse_time(""), None);
+        assert_eq!(parse_time("2026101"), None);
+        assert_eq!(parse_time("20261318123000 +0000"), None);
+        assert_eq!(parse_time("20261018123000 +8"), None);
+        assert_eq!(parse_time("20261018123000 CST"), None);
+    }
+
+    #[test]
+    fn parses_channels_and_programmes() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+  <channel id="cctv1"><display-name>CCTV-1 综合</display-name><icon src="http://logo/cctv1.png"/></channel>
+  <programme channel="cctv1" start="20261018210000 +0800" stop="20261018213000 +0800"><title>晚间</title></programme>
+  <programme channel="cctv1" start="20261018203000 +0800" stop="20261018210000 +0800">
+    <title>新闻 &amp; 天气</title><category>新闻</category>
+  </programme>
+  <programme channel="cctv1" start="bad"><title>跳过</title></programme>
+</tv>"#;
+        let guide = parse(xml.as_bytes()).unwrap();
+        assert_eq!(guide.channels.len(), 1);
+        assert_eq!(guide.channels[0].display_names, ["CCTV-1 综合"]);
+        assert_eq!(guide.channels[0].icon.as_deref(), Some("http://logo/cctv1.png"));
+
+        let programmes = &guide.programmes["cctv1"];
+        let titles: Vec<&str> = programmes.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["新闻 & 天气", "晚间"]);
+        assert_eq!(programmes[0].start, NOON_UTC);
+        assert_eq!(programmes[0].stop, NOON_UTC + 1800);
+        assert_eq!(programmes[0].categories, ["新闻"]);
+    }
+}
@@ -0,0 +1,636 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::playlist::Playlist;
+use crate::{Channel, PlaylistMeta, XtreamAccount};
+
+impl XtreamAccount {
+    /// 服务器根地址：去掉末尾的 `/` 和误填的 `player_api.php`，没有协议时补上 `http://`
+    ///
+    /// 地址可以带路径前缀（例如本地模拟服务器的 `http://127.0.0.1:8080/xc`）。
+    pub fn base_url(&self) -> String {
+        let server = self.server.trim().trim_end_matches('/');
+        let server = server.strip_suffix("/player_api.php").unwrap_or(server);
+        if server.contains("://") {
+            server.to_string()
+        } else {
+            format!("http://{}", server)
+        }
+    }
+
+    fn api_url(&self, action: Option<&str>, params: &[(&str, &str)]) -> String {
+        let mut url = format!(
+            "{}/player_api.php?username={}&password={}",
+            self.base_url(),
+            urlencoding::encode(&self.username),
+            urlencoding::encode(&self.password)
+        );
+        if let Some(action) = action {
+            url.push_str("&action=");
+            url.push_str(action);
+        }
+        for (key, value) in params {
+            url.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+        }
+        url
+    }
+
+    /// 账号对应的 XMLTV 节目单地址
+    pub fn xmltv_url(&self) -> String {
+        format!(
+            "{}/xmltv.php?username={}&password={}",
+            self.base_url(),
+            urlencoding::encode(&self.username),
+            urlencoding::encode(&self.password)
+        )
+    }
+
+    /// 播放地址：`{server}/{kind}/{user}/{pass}/{id}.{ext}`，kind 为 live / movie / series
+    pub fn stream_url(&self, kind: &str, stream_id: &str, extension: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/{}.{}",
+            self.base_url(),
+            kind,
+            urlencoding::encode(&self.username),
+            urlencoding::encode(&self.password),
+            stream_id,
+            extension
+        )
+    }
+
+    /// 调用 `player_api.php`，日志中不记录带账号密码的完整地址
+    pub async fn request(&self, client: &reqwest::Client, action: Option<&str>, params: &[(&str, &str)]) -> Result<Value, String> {
+        debug!("Xtream 请求: action={:?}", action);
+
+        let response = client
+            .get(self.api_url(action, params))
+            .send()
+            .await
+            .map_err(|e| format!("Xtream 请求失败: {}", e.without_url()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Xtream 服务器返回错误状态: {}", status));
+        }
+
+        response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Xtream 返回的数据无法解析: {}", e.without_url()))
+    }
+
+    /// 验证账号，返回服务器允许的输出格式（m3u8 / ts）
+    pub async fn authenticate(&self, client: &reqwest::Client) -> Result<Vec<String>, String> {
+        let info = self.request(client, None, &[]).await?;
+        let user_info = info.get("user_info").ok_or("Xtream 服务器没有返回账号信息")?;
+
+        if field_u32(user_info, "auth") != Some(1) {
+            return Err("Xtream 账号认证失败，请检查用户名和密码".to_string());
+        }
+        if let Some(status) = field_string(user_info, "status") {
+            if !status.eq_ignore_ascii_case("active") {
+                return Err(format!("Xtream 账号状态异常: {}", status));
+            }
+        }
+
+        let formats = user_info
+            .get("allowed_output_formats")
+            .and_then(Value::as_array)
+            .map(|formats| formats.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default();
+        Ok(formats)
+    }
+}
+
+/// 创建访问 Xtream 接口的客户端
+pub fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("创建客户端失败: {}", e))
+}
+
+/// 字段可能是字符串也可能是数字，统一读取为字符串
+pub fn field_string(value: &Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                Some(trimmed.to_string())
+            }
+        }
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+pub fn field_u32(value: &Value, key: &str) -> Option<u32> {
+    field_string(value, key)?.parse().ok()
+}
+
+/// `[{category_id, category_name}]` -> id 到名称的映射
+pub fn category_names(categories: &Value) -> HashMap<String, String> {
+    categories
+        .as_array()
+        .map(|categories| {
+            categories
+                .iter()
+                .filter_map(|c| Some((field_string(c, "category_id")?, field_string(c, "category_name")?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 拉取账号下的全部直播频道
+///
+/// 分类名作为分组，`stream_icon` 作为台标，`epg_channel_id` 作为 tvg-id；
+/// 支持回看（`tv_archive`）的频道使用 xc 回看模式。
+pub async fn fetch_live_playlist(account: &XtreamAccount, client: &reqwest::Client) -> Result<Playlist, String> {
+    let formats = account.authenticate(client).await?;
+    // 播放器基于 HLS，服务器允许时优先使用 m3u8
+    let extension = if formats.is_empty() || formats.iter().any(|f| f == "m3u8") {
+        "m3u8"
+    } else {
+        "ts"
+    };
+    debug!("Xtream 允许的输出格式: {:?}，使用 {}", formats, extension);
+
+    let (categories, streams) = tokio::join!(
+        account.request(client, Some("get_live_categories"), &[]),
+        account.request(client, Some("get_live_streams"), &[]),
+    );
+    let categories = category_names(&categories?);
+    let streams = streams?;
+    let streams = streams.as_array().ok_or("Xtream 直播列表格式错误")?;
+
+    let mut channels = Vec::with_capacity(streams.len());
+    for stream in streams {
+        let Some(stream_id) = field_string(stream, "stream_id") else {
+            warn!("跳过没有 stream_id 的直播条目");
+            continue;
+        };
+
+        let archive = field_u32(stream, "tv_archive") == Some(1);
+        channels.push(Channel {
+            name: field_string(stream, "name").unwrap_or_else(|| "未命名频道".to_string()),
+            url: account.stream_url("live", &stream_id, extension),
+            logo: field_string(stream, "stream_icon"),
+            group: field_string(stream, "category_id").and_then(|id| categories.get(&id).cloned()),
+            tvg_id: field_string(stream, "epg_channel_id"),
+            tvg_chno: field_u32(stream, "num"),
+            catchup: archive.then(|| "xc".to_string()),
+            catchup_days: if archive { field_u32(stream, "tv_archive_duration") } else { None },
+            ..Default::default()
+        });
+    }
+
+    if channels.is_empty() {
+        warn!("Xtream 账号下没有直播频道");
+        return Err("未找到有效的频道信息".to_string());
+    }
+
+    info!("成功从 Xtream 获取 {} 个直播频道，{} 个分类", channels.len(), categories.len());
+    Ok(Playlist {
+        meta: PlaylistMeta {
+            epg_urls: vec![account.xmltv_url()],
+            ..Default::default()
+        },
+        channels,
+        encoding: None,
+        report: Default::default(),
+    })
+}
+
+/// 点播 / 剧集分类
+#[derive(Debug, Clone, Serialize)]
+pub struct XtreamCategory {
+    pub id: String,
+    pub name: String,
+}
+
+/// 点播影片
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VodItem {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+    /// 时长（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u32>,
+    /// 容器格式（mp4 / mkv ...）
+    pub extension: String,
+    /// 经过本地代理的播放地址
+    pub url: String,
+}
+
+/// 剧集
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeriesItem {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cast: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+}
+
+/// 剧集的一季
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesSeason {
+    pub number: u32,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    pub episodes: Vec<Episode>,
+}
+
+/// 单集
+#[derive(Debug, Clone, Serialize)]
+pub struct Episode {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<u32>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u32>,
+    pub extension: String,
+    /// 经过本地代理的播放地址
+    pub url: String,
+}
+
+/// 剧集详情（`get_series_info`）
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesDetail {
+    pub series: SeriesItem,
+    pub seasons: Vec<SeriesSeason>,
+}
+
+/// 时长可能是秒数（`duration_secs`）或 `HH:MM:SS`（`duration`）
+fn duration_field(info: &Value) -> Option<u32> {
+    if let Some(secs) = field_u32(info, "duration_secs").filter(|secs| *secs > 0) {
+        return Some(secs);
+    }
+    let duration = field_string(info, "duration")?;
+    let mut total = 0u32;
+    for part in duration.split(':') {
+        total = total.checked_mul(60)?.checked_add(part.trim().parse().ok()?)?;
+    }
+    (total > 0).then_some(total)
+}
+
+fn array_of(value: Value, what: &str) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items),
+        _ => Err(format!("Xtream {}格式错误", what)),
+    }
+}
+
+fn category_param(category_id: Option<&str>) -> Vec<(&str, &str)> {
+    category_id.map(|id| vec![("category_id", id)]).unwrap_or_default()
+}
+
+/// 点播或剧集的分类列表，`kind` 为 `vod` 或 `series`
+pub async fn fetch_categories(account: &XtreamAccount, kind: &str) -> Result<Vec<XtreamCategory>, String> {
+    let action = match kind {
+        "vod" => "get_vod_categories",
+        "series" => "get_series_categories",
+        "live" => "get_live_categories",
+        other => return Err(format!("不支持的分类类型: {}", other)),
+    };
+
+    let categories = array_of(account.request(&client()?, Some(action), &[]).await?, "分类列表")?;
+    Ok(categories
+        .iter()
+        .filter_map(|c| {
+            Some(XtreamCategory {
+                id: field_string(c, "category_id")?,
+                name: field_string(c, "category_name")?,
+            })
+        })
+        .collect())
+}
+
+impl XtreamAccount {
+    fn vod_item(&self, stream: &Value) -> Option<VodItem> {
+        let id = field_string(stream, "stream_id")?;
+        let extension = field_string(stream, "container_extension").unwrap_or_else(|| "mp4".to_string());
+        Some(VodItem {
+            url: crate::build_proxy_url(&self.stream_url("movie", &id, &extension), None),
+            name: field_string(stream, "name").unwrap_or_else(|| id.clone()),
+            category_id: field_string(stream, "category_id"),
+            poster: field_string(stream, "stream_icon").or_else(|| field_string(stream, "cover")),
+            plot: field_string(stream, "plot"),
+            genre: field_string(stream, "genre"),
+            rating: field_string(stream, "rating"),
+            release_date: field_string(stream, "releasedate").or_else(|| field_string(stream, "releaseDate")),
+            duration_secs: duration_field(stream),
+            id,
+            extension,
+        })
+    }
+
+    fn series_item(series: &Value) -> Option<SeriesItem> {
+        Some(SeriesItem {
+            id: field_string(series, "series_id")?,
+            name: field_string(series, "name").unwrap_or_default(),
+            category_id: field_string(series, "category_id"),
+            poster: field_string(series, "cover"),
+            plot: field_string(series, "plot"),
+            genre: field_string(series, "genre"),
+            cast: field_string(series, "cast"),
+            rating: field_string(series, "rating"),
+            release_date: field_string(series, "releaseDate").or_else(|| field_string(series, "releasedate")),
+        })
+    }
+
+    fn episode(&self, episode: &Value, fallback_poster: Option<&String>) -> Option<Episode> {
+        let id = field_string(episode, "id")?;
+        let extension = field_string(episode, "container_extension").unwrap_or_else(|| "mp4".to_string());
+        // 没有详情时 info 可能是空数组
+        let info = episode.get("info").filter(|info| info.is_object());
+        Some(Episode {
+            url: crate::build_proxy_url(&self.stream_url("series", &id, &extension), None),
+            episode: field_u32(episode, "episode_num"),
+            title: field_string(episode, "title").unwrap_or_else(|| id.clone()),
+            plot: info.and_then(|info| field_string(info, "plot")),
+            poster: info
+                .and_then(|info| field_string(info, "movie_image"))
+                .or_else(|| fallback_poster.cloned()),
+            duration_secs: info.and_then(duration_field),
+            id,
+            extension,
+        })
+    }
+}
+
+/// 点播影片列表，可按分类过滤
+pub async fn fetch_vod_streams(account: &XtreamAccount, category_id: Option<&str>) -> Result<Vec<VodItem>, String> {
+    let streams = account
+        .request(&client()?, Some("get_vod_streams"), &category_param(category_id))
+        .await?;
+    let items: Vec<VodItem> = array_of(streams, "点播列表")?
+        .iter()
+        .filter_map(|stream| account.vod_item(stream))
+        .collect();
+    info!("获取到 {} 个点播影片", items.len());
+    Ok(items)
+}
+
+/// 单个影片的详情（`get_vod_info`），补充简介、海报和时长
+pub async fn fetch_vod_info(account: &XtreamAccount, stream_id: &str) -> Result<VodItem, String> {
+    let detail = account
+        .request(&client()?, Some("get_vod_info"), &[("vod_id", stream_id)])
+        .await?;
+    let info = detail.get("info").filter(|info| info.is_object());
+    let movie = detail.get("movie_data").ok_or("Xtream 没有返回影片信息")?;
+
+    let mut item = account.vod_item(movie).ok_or("Xtream 影片信息缺少 stream_id")?;
+    if let Some(info) = info {
+        item.name = field_string(info, "name").unwrap_or(item.name);
+        item.poster = field_string(info, "movie_image").or(item.poster);
+        item.plot = field_string(info, "plot").or(item.plot);
+        item.genre = field_string(info, "genre").or(item.genre);
+        item.rating = field_string(info, "rating").or(item.rating);
+        item.release_date = field_string(info, "releasedate").or(item.release_date);
+        item.duration_secs = duration_field(info).or(item.duration_secs);
+    }
+    Ok(item)
+}
+
+/// 剧集列表，可按分类过滤
+pub async fn fetch_series(account: &XtreamAccount, category_id: Option<&str>) -> Result<Vec<SeriesItem>, String> {
+    let series = account
+        .request(&client()?, Some("get_series"), &category_param(category_id))
+        .await?;
+    let items: Vec<SeriesItem> = array_of(series, "剧集列表")?
+        .iter()
+        .filter_map(XtreamAccount::series_item)
+        .collect();
+    info!("获取到 {} 部剧集", items.len());
+    Ok(items)
+}
+
+/// 剧集详情：按季分组的分集列表
+pub async fn fetch_series_info(account: &XtreamAccount, series_id: &str) -> Result<SeriesDetail, String> {
+    let detail = account
+        .request(&client()?, Some("get_series_info"), &[("series_id", series_id)])
+        .await?;
+
+    let info = detail.get("info").filter(|info| info.is_object());
+    let mut series = info
+        .and_then(|info| {
+            let mut with_id = info.clone();
+            with_id["series_id"] = Value::String(series_id.to_string());
+            XtreamAccount::series_item(&with_id)
+        })
+        .unwrap_or_else(|| SeriesItem {
+            id: series_id.to_string(),
+            ..Default::default()
+        });
+    if series.name.is_empty() {
+        series.name = series_id.to_string();
+    }
+
+    // seasons 给出季名和封面，episodes 是 { "季号": [分集] }
+    let mut seasons: Vec<SeriesSeason> = detail
+        .get("seasons")
+        .and_then(Value::as_array)
+        .map(|seasons| {
+            seasons
+                .iter()
+                .filter_map(|season| {
+                    let number = field_u32(season, "season_number")?;
+                    Some(SeriesSeason {
+                        number,
+                        name: field_string(season, "name").unwrap_or_else(|| format!("第 {} 季", number)),
+                        poster: field_string(season, "cover_big").or_else(|| field_string(season, "cover")),
+                        episodes: Vec::new(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(episodes) = detail.get("episodes").and_then(Value::as_object) {
+        for (season_key, list) in episodes {
+            let Ok(number) = season_key.trim().parse::<u32>() else {
+                warn!("跳过无法识别的季号: {}", season_key);
+                continue;
+            };
+            let index = match seasons.iter().position(|s| s.number == number) {
+                Some(index) => index,
+                None => {
+                    seasons.push(SeriesSeason {
+                        number,
+                        name: format!("第 {} 季", number),
+                        poster: None,
+                        episodes: Vec::new(),
+                    });
+                    seasons.len() - 1
+                }
+            };
+
+            let fallback_poster = seasons[index].poster.clone().or_else(|| series.poster.clone());
+            let parsed: Vec<Episode> = list
+                .as_array()
+                .map(|list| {
+                    list.iter()
+                        .filter_map(|episode| account.episode(episode, fallback_poster.as_ref()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            seasons[index].episodes.extend(parsed);
+        }
+    }
+
+    // 没有分集的季（部分服务器会列出尚未上线的季）不返回
+    seasons.retain(|season| !season.episodes.is_empty());
+    seasons.sort_by_key(|season| season.number);
+    for season in &mut seasons {
+        season.episodes.sort_by_key(|episode| episode.episode.unwrap_or(u32::MAX));
+    }
+
+    info!("剧集 '{}' 共 {} 季", series.name, seasons.len());
+    Ok(SeriesDetail { series, seasons })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    /// 模拟 `player_api.php`：密码为 `secret` 时认证成功，ID 混用数字和字符串
+    async fn player_api(Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+        let authorized = params.get("password").map(String::as_str) == Some("secret");
+        let body = match params.get("action").map(String::as_str) {
+            None => json!({
+                "user_info": {
+                    "auth": if authorized { 1 } else { 0 },
+                    "status": "Active",
+                    "allowed_output_formats": ["ts"],
+                },
+            }),
+            Some("get_live_categories") => json!([
+                { "category_id": "1", "category_name": "央视频道" },
+                { "category_id": 2, "category_name": "卫视频道" },
+            ]),
+            Some("get_live_streams") => json!([
+                {
+                    "num": 1, "name": "CCTV1", "stream_id": 101, "stream_icon": "http://logo/cctv1.png",
+                    "epg_channel_id": "cctv1.cn", "category_id": 1, "tv_archive": 1, "tv_archive_duration": "7",
+                },
+                {
+                    "num": "2", "name": "湖南卫视", "stream_id": "202", "stream_icon": "",
+                    "epg_channel_id": null, "category_id": "2", "tv_archive": 0,
+                },
+                { "name": "没有 ID" },
+            ]),
+            Some(_) => json!([]),
+        };
+        Json(body)
+    }
+
+    /// 在随机端口启动模拟服务器，返回服务器地址
+    async fn start_server() -> String {
+        let app = Router::new().route("/xc/player_api.php", get(player_api));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/xc", addr)
+    }
+
+    fn account(server: &str, password: &str) -> XtreamAccount {
+        XtreamAccount {
+            server: server.to_string(),
+            username: "user".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[test]
+    fn base_url_normalizes_server() {
+        assert_eq!(account("example.com:8080/", "p").base_url(), "http://example.com:8080");
+        assert_eq!(account("https://example.com/player_api.php", "p").base_url(), "https://example.com");
+        assert_eq!(account("http://127.0.0.1:8080/xc/", "p").base_url(), "http://127.0.0.1:8080/xc");
+    }
+
+    #[test]
+    fn field_string_accepts_numbers_and_strings() {
+        let value = json!({ "a": 12, "b": " 34 ", "c": "", "d": null, "e": 1.5 });
+        assert_eq!(field_string(&value, "a").as_deref(), Some("12"));
+        assert_eq!(field_string(&value, "b").as_deref(), Some("34"));
+        assert_eq!(field_string(&value, "c"), None);
+        assert_eq!(field_string(&value, "d"), None);
+        assert_eq!(field_u32(&value, "b"), Some(34));
+        assert_eq!(field_u32(&value, "e"), None);
+    }
+
+    #[tokio::test]
+    async fn authentication_failure_is_reported() {
+        let server = start_server().await;
+        let error = fetch_live_playlist(&account(&server, "wrong"), &client().unwrap()).await.unwrap_err();
+        assert!(error.contains("认证失败"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn live_streams_become_channels() {
+        let server = start_server().await;
+        let account = account(&server, "secret");
+        let playlist = fetch_live_playlist(&account, &client().unwrap()).await.unwrap();
+
+        // 服务器只允许 ts 时使用 ts
+        let urls: Vec<&str> = playlist.channels.iter().map(|c| c.url.as_str()).collect();
+        assert_eq!(urls, [format!("{}/live/user/secret/101.ts", server), format!("{}/live/user/secret/202.ts", server)]);
+
+        let cctv1 = &playlist.channels[0];
+        assert_eq!(cctv1.name, "CCTV1");
+        assert_eq!(cctv1.group.as_deref(), Some("央视频道"));
+        assert_eq!(cctv1.logo.as_deref(), Some("http://logo/cctv1.png"));
+        assert_eq!(cctv1.tvg_id.as_deref(), Some("cctv1.cn"));
+        assert_eq!(cctv1.tvg_chno, Some(1));
+        assert_eq!(cctv1.catchup.as_deref(), Some("xc"));
+        assert_eq!(cctv1.catchup_days, Some(7));
+
+        let hunan = &playlist.channels[1];
+        assert_eq!(hunan.group.as_deref(), Some("卫视频道"));
+        assert_eq!(hunan.logo, None);
+        assert_eq!(hunan.tvg_id, None);
+        assert_eq!(hunan.tvg_chno, Some(2));
+        assert_eq!(hunan.catchup, None);
+
+        assert_eq!(playlist.meta.epg_urls, [format!("{}/xmltv.php?username=user&password=secret", server)]);
+    }
+}
@@ -1,41 +1,363 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { save } from "@tauri-apps/plugin-dialog";
 import SourceList from "./components/SourceList";
 import ChannelList from "./components/ChannelList";
 import VideoPlayer from "./components/VideoPlayer";
 import AddSource from "./components/AddSource";
+import EpgSchedule from "./components/EpgSchedule";
+import EpgSettings from "./components/EpgSettings";
 import "./App.css";
 import "./CollapseStyles.css";
 
+export interface HttpOptions {
+  user_agent?: string;
+  referrer?: string;
+  headers?: Record<string, string>;
+}
+
 export interface Channel {
   name: string;
   url: string;
+  backup_urls?: string[]; // 合并重复频道后的备用地址
   logo?: string;
   group?: string;
+  tvg_id?: string;
+  tvg_name?: string;
+  tvg_chno?: number;
+  tvg_shift?: number;
+  catchup?: string;
+  catchup_source?: string;
+  catchup_days?: number;
+  attributes?: Record<string, string>; // 其他 #EXTINF 属性
+  http?: HttpOptions; // #EXTVLCOPT / #KODIPROP 请求头
+  vlc_options?: Record<string, string>;
+  kodi_props?: Record<string, string>;
+  variants?: HlsVariant[]; // HLS 主播放列表中的清晰度，按码率从高到低
+}
+
+export interface HlsVariant {
+  url: string;
+  bandwidth?: number;
+  average_bandwidth?: number;
+  resolution?: string;
+  codecs?: string;
+  frame_rate?: number;
+}
+
+export interface PlaylistMeta {
+  epg_urls?: string[];
+  catchup?: string;
+  catchup_source?: string;
+  catchup_days?: number;
+  tvg_shift?: number;
+  user_agent?: string;
+  attributes?: Record<string, string>; // 其他 #EXTM3U 属性
 }
 
 export interface Source {
   id: string;
   name: string;
-  url: string;
+  kind: SourceKind; // 订阅源的来源
   channels: Channel[];
-  filePath?: string; // 本地文件的原始路径
+  meta?: PlaylistMeta; // #EXTM3U 头部信息
+  merge_duplicates?: boolean; // 是否合并重复频道
+  encoding?: string; // 检测到的原始编码（UTF-8 / GBK 等）
+  refresh_interval?: number; // 自动刷新间隔（分钟）
+  last_refreshed?: number; // 最近一次成功加载的时间（Unix 秒）
+  last_error?: string; // 最近一次刷新失败的原因
+  channel_count?: number; // 最近一次成功加载得到的频道数
+  http_settings?: SourceHttpSettings; // 下载播放列表时的 User-Agent、请求头、认证等
+  watch_file?: boolean; // 本地文件变化时自动重新加载
+  file_missing?: boolean; // 本地文件已不存在（保留原有频道）
+  live_entry?: LiveEntry; // 从 TVBox 配置导入时的直播条目
+}
+
+// TVBox 配置中的直播条目，重新加载后再次应用其中的 EPG 和台标模板
+export interface LiveEntry {
+  name: string;
+  url: string;
+  user_agent?: string;
+  epg?: string;
+  logo?: string; // 台标模板，{name} 替换为频道名
+}
+
+// User-Agent 预设，custom 时 value 为自定义的值
+export type UserAgentPreset =
+  | { type: "default" }
+  | { type: "chrome" }
+  | { type: "vlc" }
+  | { type: "kodi" }
+  | { type: "tivimate" }
+  | { type: "okhttp" }
+  | { type: "custom"; value: string };
+
+export type HttpAuth =
+  | { type: "basic"; username: string; password: string }
+  | { type: "bearer"; token: string };
+
+// 订阅源的 HTTP 请求设置
+export interface SourceHttpSettings {
+  user_agent?: UserAgentPreset;
+  headers?: Record<string, string>;
+  auth?: HttpAuth;
+  timeout_secs?: number; // 连接和读取超时（秒），默认 30
+  accept_invalid_certs?: boolean; // 接受无效的 HTTPS 证书
+  apply_to_streams?: boolean; // 播放该订阅源的频道时也使用这些设置
+}
+
+// 订阅源刷新结果（source-refreshed 事件）
+export interface RefreshResult {
+  source_id: string;
+  name: string;
+  success: boolean;
+  not_modified: boolean; // 服务器返回 304，内容没有变化
+  channel_count: number;
+  previous_channel_count: number;
+  error?: string;
+  refreshed_at: number;
+  trigger: "manual" | "scheduled" | "file_changed";
+}
+
+// 节目单的状态（refresh_epg / get_epg_feeds 的结果和 epg-updated 事件）
+// DIYP 接口按需查询，状态根据缓存的查询结果统计
+export interface EpgFeedStatus {
+  id: string; // 地址的标识
+  url: string; // 用于显示，密码等参数已隐藏
+  kind: "xmltv" | "diyp";
+  fetched_at?: number; // 最近一次成功下载的时间（Unix 秒）
+  channel_count: number;
+  programme_count: number;
+  error?: string; // 最近一次下载失败的原因
+}
+
+// 全局节目单设置（get_epg_settings），地址按优先级排列
+export interface EpgGlobalSettings {
+  global_urls: string[];
+  prefer_global: boolean; // 全局节目单优先于订阅源自带的节目单
+}
+
+// 频道别名：播放列表中的频道名 -> 节目单和台标使用的名称
+export interface ChannelAlias {
+  name: string;
+  target: string;
+}
+
+// 节目，时间为 Unix 时间戳（秒），已按频道的 tvg-shift 调整
+export interface Programme {
+  start: number;
+  stop: number;
+  title: string;
+  sub_title?: string;
+  desc?: string;
+  categories?: string[];
+  episode?: string;
+  icon?: string;
+}
+
+// 频道的当前节目和下一个节目（get_epg_now_next）
+export interface NowNext {
+  now?: Programme;
+  next?: Programme;
+}
+
+// 节目表中一个频道的节目（get_epg_grid）
+export interface EpgGridRow {
+  channel_url: string;
+  channel_name: string;
+  programmes: Programme[];
+}
+
+// 订阅源的来源：网络地址、本地文件、粘贴的内容、内置测试数据或 Xtream Codes 账号
+export type SourceKind =
+  | { type: "remote"; url: string }
+  | { type: "local_file"; path: string }
+  | { type: "inline"; content: string }
+  | { type: "demo" }
+  | ({ type: "xtream" } & XtreamAccount);
+
+// 用于界面显示的来源描述
+export function describeSourceKind(kind: SourceKind): string {
+  switch (kind.type) {
+    case "remote":
+      return kind.url;
+    case "local_file":
+      return kind.path;
+    case "inline":
+      return `粘贴的内容（${kind.content.length} 字符）`;
+    case "demo":
+      return "内置测试数据";
+    case "xtream":
+      return `Xtream Codes: ${kind.server}`;
+  }
+}
+
+export interface XtreamAccount {
+  server: string;
+  username: string;
+  password: string;
+}
+
+export interface ImportFailure {
+  name: string;
+  url: string;
+  error: string;
+}
+
+// 播放列表加载进度（playlist-progress 事件）
+export interface LoadProgress {
+  task_id: string;
+  url: string;
+  bytes_read: number;
+  total_bytes?: number;
+  channels: number;
+  done: boolean;
+}
+
+// 播放列表解析问题
+export type IssueKind = "skipped" | "malformed" | "duplicate_url" | "missing_name";
+
+export interface ParseIssue {
+  line: number;
+  kind: IssueKind;
+  message: string;
+}
+
+export interface ParseReport {
+  lines: number;
+  issues: ParseIssue[];
+  counts: Partial<Record<IssueKind, number>>;
+  unsupported_directives: { name: string; first_line: number; count: number }[];
+}
+
+export interface SourceDiagnostics {
+  name: string;
+  report: ParseReport;
+}
+
+export interface ImportReport {
+  added: string[];
+  failed: ImportFailure[];
+  diagnostics: SourceDiagnostics[];
+}
+
+const ISSUE_LABELS: Record<IssueKind, string> = {
+  skipped: "跳过",
+  malformed: "格式异常",
+  duplicate_url: "重复地址",
+  missing_name: "缺少名称",
+};
+
+// 最多列出的问题条数
+const MAX_LISTED_ISSUES = 10;
+
+function formatDiagnostics({ name, report }: SourceDiagnostics): string {
+  const counts = (Object.keys(ISSUE_LABELS) as IssueKind[])
+    .filter(kind => report.counts[kind])
+    .map(kind => `${ISSUE_LABELS[kind]} ${report.counts[kind]} 处`);
+  const lines = [`【${name}】${counts.join("，")}`];
+  for (const issue of report.issues.slice(0, MAX_LISTED_ISSUES)) {
+    lines.push(`• 第 ${issue.line} 行 [${ISSUE_LABELS[issue.kind]}] ${issue.message}`);
+  }
+  const total = Object.values(report.counts).reduce((sum, n) => sum + (n ?? 0), 0);
+  if (total > MAX_LISTED_ISSUES) {
+    lines.push(`• …… 另有 ${total - MAX_LISTED_ISSUES} 处`);
+  }
+  if (report.unsupported_directives.length > 0) {
+    const directives = report.unsupported_directives.map(d => `${d.name}（${d.count} 次，首次在第 ${d.first_line} 行）`);
+    lines.push(`• 不支持的指令: ${directives.join("，")}`);
+  }
+  return lines.join("\n");
 }
 
 function App() {
   const [sources, setSources] = useState<Source[]>([]);
   const [selectedSource, setSelectedSource] = useState<Source | null>(null);
   const [selectedChannel, setSelectedChannel] = useState<Channel | null>(null);
+  const [playingSourceId, setPlayingSourceId] = useState<string | null>(null); // 正在播放的频道所属的订阅源
+  const [nowNext, setNowNext] = useState<Record<string, NowNext>>({}); // 频道地址 -> 当前 / 下一个节目
   const [showAddSource, setShowAddSource] = useState(false);
+  const [showEpgSettings, setShowEpgSettings] = useState(false);
   const [editingSource, setEditingSource] = useState<Source | null>(null);
   const [sourceListCollapsed, setSourceListCollapsed] = useState(false);
   const [channelListCollapsed, setChannelListCollapsed] = useState(false);
+  const [loadProgress, setLoadProgress] = useState<LoadProgress | null>(null);
+  const activeTaskRef = useRef<string | null>(null);
+
+  // 只显示当前加载任务的进度
+  useEffect(() => {
+    const unlisten = listen<LoadProgress>("playlist-progress", (event) => {
+      if (event.payload.task_id === activeTaskRef.current) {
+        setLoadProgress(event.payload);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 带进度和取消的加载：taskId 传给后端，用于匹配进度事件和取消
+  const runWithProgress = async <T,>(task: (taskId: string) => Promise<T>): Promise<T> => {
+    const taskId = crypto.randomUUID();
+    activeTaskRef.current = taskId;
+    setLoadProgress({ task_id: taskId, url: "", bytes_read: 0, channels: 0, done: false });
+    try {
+      return await task(taskId);
+    } finally {
+      activeTaskRef.current = null;
+      setLoadProgress(null);
+    }
+  };
+
+  const handleCancelLoad = async () => {
+    if (activeTaskRef.current) {
+      await invoke("cancel_playlist_load", { taskId: activeTaskRef.current });
+    }
+  };
 
   useEffect(() => {
     loadSources();
+
+    // 手动刷新和后台定时刷新完成后重新加载列表
+    const unlisten = listen<RefreshResult>("source-refreshed", (event) => {
+      console.log("🔄 订阅源已刷新:", event.payload);
+      loadSources();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
+  // 选中订阅源的当前节目：每分钟和节目单更新后重新加载
+  useEffect(() => {
+    if (!selectedSource) {
+      setNowNext({});
+      return;
+    }
+    const sourceId = selectedSource.id;
+    const urls = selectedSource.channels.map((c) => c.url);
+    let cancelled = false;
+    const load = async () => {
+      try {
+        const result = await invoke<Record<string, NowNext>>("get_epg_now_next", { sourceId, urls });
+        if (!cancelled) setNowNext(result);
+      } catch (error) {
+        console.error("❌ 加载当前节目失败:", error);
+      }
+    };
+    load();
+
+    const timer = setInterval(load, 60_000);
+    const unlisten = listen("epg-updated", load);
+    return () => {
+      cancelled = true;
+      clearInterval(timer);
+      unlisten.then((fn) => fn());
+    };
+  }, [selectedSource]);
+
   const loadSources = async () => {
     try {
       console.log("🔄 正在加载订阅源列表...");
@@ -44,6 +366,8 @@ function App() {
       console.log("📊 订阅源列表:", loadedSources);
 
       setSources(loadedSources);
+      // 选中的订阅源替换为最新数据（刷新后频道会变化）
+      setSelectedSource((current) => (current && loadedSources.find((s) => s.id === current.id)) || current);
       if (loadedSources.length > 0 && !selectedSource) {
         setSelectedSource(loadedSources[0]);
         console.log("✅ 自动选中第一个订阅源:", loadedSources[0].name);
@@ -53,15 +377,29 @@ function App() {
     }
   };
 
-  const handleAddSource = async (name: string, url: string) => {
+  const handleAddSource = async (name: string, kind: SourceKind, mergeDuplicates: boolean, refreshInterval: number | null, watchFile: boolean, httpSettings: SourceHttpSettings | null) => {
     try {
       console.log("=== 开始添加订阅源 ===");
       console.log("名称:", name);
-      console.log("URL:", url);
+      console.log("来源:", describeSourceKind(kind));
       console.log("Tauri 环境:", !!(window as any).__TAURI__);
 
-      await invoke("add_source", { name, url });
-      console.log("✅ 添加成功，开始加载订阅源列表...");
+      const report = await runWithProgress((taskId) =>
+        invoke<ImportReport>("create_source", { name, kind, mergeDuplicates, refreshInterval, watchFile, httpSettings, taskId })
+      );
+      console.log("✅ 添加成功，开始加载订阅源列表...", report);
+
+      // TVBox 配置可能部分条目导入失败
+      if (report.failed.length > 0) {
+        const details = report.failed.map(f => `• ${f.name}: ${f.error}`).join("\n");
+        alert(`已导入 ${report.added.length} 个订阅源，${report.failed.length} 个失败:\n${details}`);
+      }
+
+      // 有效频道已导入，提示被跳过或不规范的条目
+      if (report.diagnostics.length > 0) {
+        const details = report.diagnostics.map(formatDiagnostics).join("\n\n");
+        alert(`订阅源已导入，解析时发现以下问题:\n\n${details}`);
+      }
 
       await loadSources();
       console.log("✅ 订阅源列表已刷新");
@@ -113,12 +451,84 @@ function App() {
     }
   };
 
+  const handleRefreshSource = async (source: Source) => {
+    try {
+      const result = await runWithProgress((taskId) =>
+        invoke<RefreshResult>("refresh_source", { sourceId: source.id, taskId })
+      );
+      if (!result.success) {
+        alert(`刷新订阅源 "${source.name}" 失败，已保留原有频道:\n${result.error}`);
+      }
+    } catch (error) {
+      console.error("❌ 刷新订阅源失败:", error);
+      alert(`刷新订阅源失败: ${error}`);
+    }
+  };
+
+  const handleRefreshAll = async () => {
+    try {
+      const results = await runWithProgress((taskId) => invoke<RefreshResult[]>("refresh_all", { taskId }));
+      const failed = results.filter((r) => !r.success);
+      if (failed.length > 0) {
+        const details = failed.map((r) => `• ${r.name}: ${r.error}`).join("\n");
+        alert(`已刷新 ${results.length - failed.length} 个订阅源，${failed.length} 个失败（保留原有频道）:\n${details}`);
+      }
+    } catch (error) {
+      console.error("❌ 刷新全部订阅源失败:", error);
+      alert(`刷新全部订阅源失败: ${error}`);
+    }
+  };
+
   const handleEditSource = (source: Source) => {
     setEditingSource(source);
     setShowAddSource(true);
   };
 
-  const handleUpdateSource = async (name: string, url: string) => {
+  const handleRefreshEpg = async (source: Source) => {
+    try {
+      const feeds = await invoke<EpgFeedStatus[]>("refresh_epg", { sourceId: source.id });
+      const failed = feeds.filter((f) => f.error);
+      if (failed.length > 0) {
+        alert(`节目单下载失败:\n${failed.map((f) => `• ${f.url}: ${f.error}`).join("\n")}`);
+      } else {
+        const programmes = feeds.reduce((sum, f) => sum + f.programme_count, 0);
+        alert(`节目单已更新: ${feeds.length} 个地址，${programmes} 个节目`);
+      }
+    } catch (error) {
+      console.error("❌ 刷新节目单失败:", error);
+      alert(`刷新节目单失败: ${error}`);
+    }
+  };
+
+  const handleExportSource = async (source: Source) => {
+    try {
+      const path = await save({
+        defaultPath: `${source.name}.m3u`,
+        filters: [
+          { name: "M3U", extensions: ["m3u", "m3u8"] },
+          { name: "DIYP TXT", extensions: ["txt"] },
+          { name: "JSON", extensions: ["json"] },
+        ],
+      });
+      if (!path) return;
+
+      // 按文件扩展名选择导出格式
+      const extension = path.split(".").pop()?.toLowerCase();
+      const format = extension === "txt" ? "txt" : extension === "json" ? "json" : "m3u";
+      const count = await invoke<number>("export_source", {
+        sourceId: source.id,
+        format,
+        path,
+        filter: { merge_duplicates: !!source.merge_duplicates },
+      });
+      alert(`已导出 ${count} 个频道到:\n${path}`);
+    } catch (error) {
+      console.error("❌ 导出订阅源失败:", error);
+      alert(`导出订阅源失败: ${error}`);
+    }
+  };
+
+  const handleUpdateSource = async (name: string, kind: SourceKind, mergeDuplicates: boolean, refreshInterval: number | null, watchFile: boolean, httpSettings: SourceHttpSettings | null) => {
     if (!editingSource) return;
 
     try {
@@ -126,14 +536,21 @@ function App() {
       console.log("🔄 开始更新订阅源");
       console.log("订阅源 ID:", editingSource.id);
       console.log("新名称:", name);
-      console.log("新 URL:", url);
+      console.log("新来源:", describeSourceKind(kind));
       console.log("========================================");
 
-      await invoke("update_source", {
-        sourceId: editingSource.id,
-        name,
-        url
-      });
+      await runWithProgress((taskId) =>
+        invoke("edit_source", {
+          sourceId: editingSource.id,
+          name,
+          kind,
+          mergeDuplicates,
+          refreshInterval,
+          watchFile,
+          httpSettings,
+          taskId
+        })
+      );
 
       console.log("✅ Tauri 命令执行完成，刷新列表");
       await loadSources();
@@ -152,6 +569,7 @@ function App() {
 
   const handleSelectChannel = (channel: Channel) => {
     setSelectedChannel(channel);
+    setPlayingSourceId(selectedSource?.id ?? null);
   };
 
   return (
@@ -165,7 +583,12 @@ function App() {
             onSelectSource={setSelectedSource}
             onDeleteSource={handleDeleteSource}
             onEditSource={handleEditSource}
+            onRefreshSource={handleRefreshSource}
+            onRefreshAll={handleRefreshAll}
+            onRefreshEpg={handleRefreshEpg}
+            onExportSource={handleExportSource}
             onAddSource={() => setShowAddSource(true)}
+            onOpenEpgSettings={() => setShowEpgSettings(true)}
           />
         ) : null}
         <button
@@ -184,6 +607,7 @@ function App() {
             <ChannelList
               channels={selectedSource.channels}
               selectedChannel={selectedChannel}
+              nowNext={nowNext}
               onSelectChannel={handleSelectChannel}
             />
           ) : (
@@ -204,7 +628,10 @@ function App() {
       {/* 右栏：视频播放器 */}
       <div className="player-panel">
         {selectedChannel ? (
-          <VideoPlayer channel={selectedChannel} />
+          <>
+            <VideoPlayer channel={selectedChannel} sourceId={playingSourceId} />
+            {playingSourceId && <EpgSchedule sourceId={playingSourceId} channel={selectedChannel} />}
+          </>
         ) : (
           <div className="player-placeholder">
             <div className="placeholder-content">
@@ -226,6 +653,38 @@ function App() {
           initialSource={editingSource}
         />
       )}
+
+      {/* 节目单设置对话框 */}
+      {showEpgSettings && <EpgSettings onClose={() => setShowEpgSettings(false)} />}
+
+      {/* 播放列表加载进度 */}
+      {loadProgress && (
+        <div className="modal-overlay">
+          <div className="modal">
+            <h2>正在加载播放列表</h2>
+            <p style={{ fontSize: "13px", color: "#ccc", wordBreak: "break-all" }}>{loadProgress.url}</p>
+            {loadProgress.total_bytes ? (
+              <progress
+                value={loadProgress.bytes_read}
+                max={loadProgress.total_bytes}
+                style={{ width: "100%" }}
+              />
+            ) : (
+              <progress style={{ width: "100%" }} />
+            )}
+            <p style={{ fontSize: "13px", color: "#ccc" }}>
+              已下载 {(loadProgress.bytes_read / 1024 / 1024).toFixed(2)} MB
+              {loadProgress.total_bytes ? ` / ${(loadProgress.total_bytes / 1024 / 1024).toFixed(2)} MB` : ""}
+              ，已解析 {loadProgress.channels.toLocaleString()} 个频道
+            </p>
+            <div className="modal-actions">
+              <button type="button" onClick={handleCancelLoad} className="secondary-btn">
+                取消
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
     </div>
   );
 }
@@ -1,11 +1,11 @@
 import { useState, useEffect } from "react";
 import { open } from "@tauri-apps/plugin-dialog";
-import { readTextFile } from "@tauri-apps/plugin-fs";
 import "../AddSourceStyles.css";
-import type { Source } from "../App";
+import { describeSourceKind } from "../App";
+import type { HttpAuth, Source, SourceHttpSettings, SourceKind, UserAgentPreset, XtreamAccount } from "../App";
 
 interface AddSourceProps {
-  onAdd: (name: string, url: string) => void;
+  onAdd: (name: string, kind: SourceKind, mergeDuplicates: boolean, refreshInterval: number | null, watchFile: boolean, httpSettings: SourceHttpSettings | null) => void;
   onClose: () => void;
   initialSource?: Source | null;
 }
@@ -13,9 +13,26 @@ interface AddSourceProps {
 function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
   const [name, setName] = useState("");
   const [url, setUrl] = useState("");
-  const [sourceType, setSourceType] = useState<"url" | "file">("url");
+  const [sourceType, setSourceType] = useState<"url" | "file" | "paste" | "xtream">("url");
   const [loading, setLoading] = useState(false);
-  const [filePath, setFilePath] = useState(""); // 保存原始文件路径
+  const [filePath, setFilePath] = useState(""); // 本地文件路径，加载时由后端读取
+  const [content, setContent] = useState(""); // 粘贴的播放列表内容
+  const [useDemo, setUseDemo] = useState(false); // 使用内置测试数据
+  const [mergeDuplicates, setMergeDuplicates] = useState(false);
+  const [refreshInterval, setRefreshInterval] = useState(0); // 自动刷新间隔（分钟），0 表示不自动刷新
+  const [watchFile, setWatchFile] = useState(false); // 本地文件变化时自动重新加载
+  const [xtream, setXtream] = useState<XtreamAccount>({ server: "", username: "", password: "" });
+  // HTTP 设置（网络地址和 Xtream）
+  const [userAgent, setUserAgent] = useState<UserAgentPreset["type"]>("default");
+  const [customUserAgent, setCustomUserAgent] = useState("");
+  const [headersText, setHeadersText] = useState(""); // 每行一个 "名称: 值"
+  const [authType, setAuthType] = useState<"none" | HttpAuth["type"]>("none");
+  const [authUsername, setAuthUsername] = useState("");
+  const [authPassword, setAuthPassword] = useState("");
+  const [authToken, setAuthToken] = useState("");
+  const [timeoutSecs, setTimeoutSecs] = useState(""); // 空表示默认 30 秒
+  const [acceptInvalidCerts, setAcceptInvalidCerts] = useState(false);
+  const [applyToStreams, setApplyToStreams] = useState(false);
   const isEditMode = !!initialSource;
 
   // 编辑模式：初始化表单数据
@@ -24,61 +41,112 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
       console.log("========================================");
       console.log("🔄 编辑模式初始化");
       console.log("订阅源名称:", initialSource.name);
-      console.log("订阅源 URL 类型:", initialSource.url.startsWith("FILE_CONTENT:") ? "本地文件" : "网络地址");
-      console.log("订阅源 URL 长度:", initialSource.url.length);
-      console.log("文件路径:", initialSource.filePath);
+      console.log("订阅源来源:", describeSourceKind(initialSource.kind));
       console.log("========================================");
 
       setName(initialSource.name);
-      setUrl(initialSource.url);
-
-      // 从 URL 中提取文件路径
-      if (initialSource.url.startsWith("FILE_CONTENT:")) {
-        setSourceType("file");
-
-        // 格式: FILE_CONTENT:<file_path>:#EXTM3U...
-        // 文件路径不会包含 #，所以找到第一个 # 之前的内容就是路径
-        const withoutPrefix = initialSource.url.substring("FILE_CONTENT:".length);
-
-        // 查找 M3U 文件开头标记 #EXTM3U
-        const contentStartMarker = withoutPrefix.indexOf('#EXTM3U');
-        if (contentStartMarker > 0) {
-          // 提取路径部分（去掉最后的冒号）
-          const pathWithColon = withoutPrefix.substring(0, contentStartMarker);
-          const extractedPath = pathWithColon.endsWith(':')
-            ? pathWithColon.substring(0, pathWithColon.length - 1)
-            : pathWithColon;
-          setFilePath(extractedPath);
-          console.log("📁 提取的文件路径:", extractedPath);
-        } else if (initialSource.filePath) {
-          setFilePath(initialSource.filePath);
-        } else {
-          // 旧数据格式，没有路径信息
-          setFilePath("(旧数据，无路径信息)");
-          console.log("⚠️ 旧数据格式，无法提取文件路径");
-        }
-      } else {
-        setSourceType("url");
-        setFilePath("");
+      setMergeDuplicates(!!initialSource.merge_duplicates);
+      setRefreshInterval(initialSource.refresh_interval ?? 0);
+      setWatchFile(!!initialSource.watch_file);
+
+      const http = initialSource.http_settings ?? {};
+      setUserAgent(http.user_agent?.type ?? "default");
+      setCustomUserAgent(http.user_agent?.type === "custom" ? http.user_agent.value : "");
+      setHeadersText(Object.entries(http.headers ?? {}).map(([key, value]) => `${key}: ${value}`).join("\n"));
+      setAuthType(http.auth?.type ?? "none");
+      setAuthUsername(http.auth?.type === "basic" ? http.auth.username : "");
+      setAuthPassword(http.auth?.type === "basic" ? http.auth.password : "");
+      setAuthToken(http.auth?.type === "bearer" ? http.auth.token : "");
+      setTimeoutSecs(http.timeout_secs ? String(http.timeout_secs) : "");
+      setAcceptInvalidCerts(!!http.accept_invalid_certs);
+      setApplyToStreams(!!http.apply_to_streams);
+
+      const kind = initialSource.kind;
+      setUrl(kind.type === "remote" ? kind.url : "");
+      setFilePath(kind.type === "local_file" ? kind.path : "");
+      setContent(kind.type === "inline" ? kind.content : "");
+      setUseDemo(kind.type === "demo");
+      switch (kind.type) {
+        case "xtream":
+          setSourceType("xtream");
+          setXtream({ server: kind.server, username: kind.username, password: kind.password });
+          break;
+        case "local_file":
+          setSourceType("file");
+          break;
+        case "inline":
+          setSourceType("paste");
+          break;
+        default:
+          setSourceType("url");
       }
     }
   }, [initialSource]);
 
+  // 根据当前标签页生成订阅源来源，信息不完整时返回 null
+  const buildKind = (): SourceKind | null => {
+    switch (sourceType) {
+      case "url":
+        if (useDemo) return { type: "demo" };
+        return url.trim() ? { type: "remote", url: url.trim() } : null;
+      case "file":
+        return filePath ? { type: "local_file", path: filePath } : null;
+      case "paste":
+        return content.trim() ? { type: "inline", content } : null;
+      case "xtream": {
+        const account: XtreamAccount = {
+          server: xtream.server.trim(),
+          username: xtream.username.trim(),
+          password: xtream.password,
+        };
+        return account.server && account.username && account.password ? { type: "xtream", ...account } : null;
+      }
+    }
+  };
+
+  // 根据表单生成 HTTP 设置，本地文件、粘贴内容和内置数据不需要
+  const buildHttpSettings = (kind: SourceKind): SourceHttpSettings | null => {
+    if (kind.type !== "remote" && kind.type !== "xtream") return null;
+
+    const headers: Record<string, string> = {};
+    for (const line of headersText.split("\n")) {
+      const separator = line.indexOf(":");
+      if (separator > 0) {
+        headers[line.slice(0, separator).trim()] = line.slice(separator + 1).trim();
+      }
+    }
+
+    let auth: HttpAuth | undefined;
+    if (authType === "basic" && authUsername) {
+      auth = { type: "basic", username: authUsername, password: authPassword };
+    } else if (authType === "bearer" && authToken.trim()) {
+      auth = { type: "bearer", token: authToken.trim() };
+    }
+
+    const timeout = Number(timeoutSecs);
+    return {
+      user_agent: userAgent === "custom" ? { type: "custom", value: customUserAgent.trim() } : { type: userAgent },
+      headers,
+      auth,
+      timeout_secs: Number.isInteger(timeout) && timeout > 0 ? timeout : undefined,
+      accept_invalid_certs: acceptInvalidCerts,
+      apply_to_streams: applyToStreams,
+    };
+  };
+
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
     console.log("========================================");
     console.log("🚀 handleSubmit 被调用");
     console.log("名称:", name);
-    console.log("URL 长度:", url.length);
-    console.log("URL 前缀:", url.substring(0, 50));
-
-    if (name.trim() && url.trim()) {
-      console.log("✅ 验证通过，调用 onAdd");
-      onAdd(name.trim(), url.trim());
+    const kind = buildKind();
+    if (name.trim() && kind) {
+      console.log("✅ 验证通过，调用 onAdd:", describeSourceKind(kind));
+      onAdd(name.trim(), kind, mergeDuplicates, refreshInterval > 0 ? refreshInterval : null, kind.type === "local_file" && watchFile, buildHttpSettings(kind));
     } else {
       console.log("❌ 验证失败");
       console.log("name.trim():", name.trim());
-      console.log("url.trim():", url.trim());
+      console.log("sourceType:", sourceType);
     }
     console.log("========================================");
   };
@@ -91,15 +159,14 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
       setLoading(true);
       console.log("📂 准备打开文件选择对话框");
       console.log("open 函数:", typeof open);
-      console.log("readTextFile 函数:", typeof readTextFile);
 
       // 打开文件选择对话框
       const selected = await open({
         multiple: false,
         filters: [
           {
-            name: "M3U Playlist",
-            extensions: ["m3u", "m3u8"],
+            name: "Playlist (M3U / TXT / TVBox JSON)",
+            extensions: ["m3u", "m3u8", "txt", "json", "gz", "tgz", "zip", "bz2", "xz", "tar"],
           },
         ],
       });
@@ -108,25 +175,12 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
       console.log("📋 结果类型:", typeof selected);
 
       if (selected && typeof selected === "string") {
-        console.log("📖 开始读取文件:", selected);
-
-        // 保存文件路径
+        // 只保存文件路径，内容由后端读取（识别编码并解压）
         setFilePath(selected);
         console.log("💾 文件路径已保存:", selected);
 
-        // 读取文件内容
-        const content = await readTextFile(selected);
-        console.log("✅ 文件读取成功");
-        console.log("📊 文件大小:", content.length, "字符");
-        console.log("📝 文件内容预览:", content.substring(0, 100));
-
-        // 使用特殊标记，告诉后端这是文件内容
-        // 格式: FILE_CONTENT:<file_path>:<content>
-        setUrl(`FILE_CONTENT:${selected}:${content}`);
-        console.log("✅ URL 已设置（包含文件路径）");
-
         // 自动从文件路径提取名称
-        const fileName = selected.split("/").pop()?.replace(/\.(m3u8?|txt)$/i, "") || "";
+        const fileName = selected.split(/[\\/]/).pop()?.replace(/\.(gz|tgz|zip|bz2|xz|tar)$/i, "").replace(/\.(m3u8?|txt|json|tar)$/i, "") || "";
         console.log("📝 提取的文件名:", fileName);
 
         if (fileName && !name) {
@@ -156,11 +210,13 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
   const handleTestUrl = () => {
     // 使用真实的测试视频流
     setUrl("https://upyun.luckly-mjw.cn/Assets/media-source/example/media/index.m3u8");
+    setUseDemo(false);
     setName("测试视频");
   };
 
   const handleUseTestData = () => {
-    setUrl("TEST_DATA");
+    setUrl("");
+    setUseDemo(true);
     setName("内置测试");
   };
 
@@ -182,7 +238,7 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
           </div>
 
           {/* 显示订阅源地址信息 - 编辑模式或已选择时显示 */}
-          {(isEditMode || (sourceType === "file" && filePath) || (sourceType === "url" && url && !url.startsWith("FILE_CONTENT:"))) && (
+          {((sourceType === "file" && filePath) || (sourceType === "url" && (url || useDemo))) && (
             <div className="form-group">
               <label>订阅源地址</label>
               <div style={{
@@ -195,7 +251,7 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
                 wordBreak: "break-all",
                 position: "relative"
               }}>
-                {url.startsWith("FILE_CONTENT:") || (sourceType === "file" && filePath) ? (
+                {sourceType === "file" ? (
                   <div>
                     <div style={{ marginBottom: "8px", color: "#4a9eff", display: "flex", justifyContent: "space-between", alignItems: "center" }}>
                       <span>📁 <strong>本地文件路径</strong></span>
@@ -230,13 +286,13 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
                       userSelect: "all",
                       cursor: "text"
                     }}>
-                      {filePath || "本地 M3U 文件内容"}
+                      {filePath}
                     </div>
                   </div>
                 ) : (
                   <div>
                     <div style={{ marginBottom: "8px", color: "#4a9eff", display: "flex", justifyContent: "space-between", alignItems: "center" }}>
-                      <span>🌐 <strong>网络地址</strong></span>
+                      <span>{useDemo ? "📦" : "🌐"} <strong>{useDemo ? "内置测试数据" : "网络地址"}</strong></span>
                       {url && (
                         <button
                           type="button"
@@ -268,7 +324,7 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
                       userSelect: "all",
                       cursor: "text"
                     }}>
-                      {url}
+                      {useDemo ? "内置的测试频道" : url}
                     </div>
                   </div>
                 )}
@@ -294,6 +350,20 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
               >
                 📁 本地文件
               </button>
+              <button
+                type="button"
+                className={`tab-btn ${sourceType === "paste" ? "active" : ""}`}
+                onClick={() => setSourceType("paste")}
+              >
+                📋 粘贴内容
+              </button>
+              <button
+                type="button"
+                className={`tab-btn ${sourceType === "xtream" ? "active" : ""}`}
+                onClick={() => setSourceType("xtream")}
+              >
+                🔑 Xtream
+              </button>
             </div>
           </div>
 
@@ -304,18 +374,14 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
               <input
                 id="source-url"
                 type="text"
-                value={url.startsWith("FILE_CONTENT:") ? "" : url}
-                onChange={(e) => setUrl(e.target.value)}
-                placeholder={isEditMode && url.startsWith("FILE_CONTENT:") ? "此订阅源来自本地文件" : "https://example.com/playlist.m3u"}
-                required={!url.startsWith("FILE_CONTENT:")}
-                disabled={isEditMode && url.startsWith("FILE_CONTENT:")}
-                style={{ opacity: isEditMode && url.startsWith("FILE_CONTENT:") ? 0.5 : 1 }}
+                value={url}
+                onChange={(e) => {
+                  setUrl(e.target.value);
+                  setUseDemo(false);
+                }}
+                placeholder={useDemo ? "使用内置测试数据" : "https://example.com/playlist.m3u"}
+                required={!useDemo}
               />
-              {isEditMode && url.startsWith("FILE_CONTENT:") && (
-                <div style={{ marginTop: "8px", padding: "8px", background: "rgba(74, 158, 255, 0.1)", borderRadius: "4px", fontSize: "12px", color: "#4a9eff" }}>
-                  📁 此订阅源来自<strong>本地文件</strong>，请切换到"📁 本地文件"标签查看详情
-                </div>
-              )}
               <div style={{ marginTop: "8px", display: "flex", gap: "8px" }}>
                 <button
                   type="button"
@@ -347,35 +413,227 @@ function AddSource({ onAdd, onClose, initialSource }: AddSourceProps) {
                 className="file-select-btn"
                 disabled={loading}
               >
-                {loading ? "读取中..." : url.startsWith("FILE_CONTENT:") ? "✅ 文件已选择" : "📁 选择 M3U 文件"}
+                {loading ? "读取中..." : filePath ? "✅ 文件已选择" : "📁 选择 M3U / TXT 文件"}
               </button>
-              {url.startsWith("FILE_CONTENT:") && (
+              {filePath && (
                 <div style={{ marginTop: "12px", padding: "12px", background: "rgba(74, 158, 255, 0.15)", borderRadius: "6px", border: "1px solid rgba(74, 158, 255, 0.3)" }}>
                   <div style={{ fontSize: "13px", color: "#4a9eff", marginBottom: "8px" }}>
-                    📦 <strong>文件内容已加载</strong>
+                    📦 <strong>文件已选择</strong>
                   </div>
                   {!isEditMode && (
                     <div style={{ fontSize: "12px", color: "#ccc", marginBottom: "8px", padding: "8px", background: "rgba(0, 0, 0, 0.2)", borderRadius: "4px" }}>
                       <div style={{ marginBottom: "4px", opacity: 0.7 }}>📁 来源类型:</div>
-                      <div style={{ fontFamily: "monospace" }}>本地 M3U 文件内容</div>
+                      <div style={{ fontFamily: "monospace" }}>本地文件（每次加载时重新读取）</div>
                     </div>
                   )}
-                  <div style={{ fontSize: "12px", color: "#ccc", marginBottom: "4px" }}>
-                    📊 文件大小: <strong>{(url.length - 13).toLocaleString()}</strong> 字符
-                  </div>
                   <div style={{ fontSize: "12px", color: "#ccc", marginBottom: "4px" }}>
                     📺 频道数量: <strong>{initialSource?.channels.length || 0}</strong> 个
                   </div>
                   {isEditMode && (
                     <div style={{ marginTop: "8px", paddingTop: "8px", borderTop: "1px solid rgba(74, 158, 255, 0.2)", fontSize: "12px", color: "#888" }}>
-                      💡 提示: 点击上方按钮可重新选择文件替换当前内容
+                      💡 提示: 点击上方按钮可重新选择文件
                     </div>
                   )}
                 </div>
               )}
+              <label style={{ display: "flex", alignItems: "center", gap: "8px", cursor: "pointer", marginTop: "12px" }}>
+                <input
+                  type="checkbox"
+                  checked={watchFile}
+                  onChange={(e) => setWatchFile(e.target.checked)}
+                />
+                文件变化时自动重新加载
+              </label>
+            </div>
+          )}
+
+          {/* 粘贴内容 */}
+          {sourceType === "paste" && (
+            <div className="form-group">
+              <label htmlFor="source-content">播放列表内容</label>
+              <textarea
+                id="source-content"
+                value={content}
+                onChange={(e) => setContent(e.target.value)}
+                placeholder={"#EXTM3U\n#EXTINF:-1,CCTV-1\nhttp://example.com/cctv1.m3u8"}
+                rows={8}
+                required
+                style={{ width: "100%", fontFamily: "monospace", fontSize: "12px", resize: "vertical" }}
+              />
             </div>
           )}
 
+          {/* Xtream Codes 账号 */}
+          {sourceType === "xtream" && (
+            <>
+              <div className="form-group">
+                <label htmlFor="xtream-server">服务器地址</label>
+                <input
+                  id="xtream-server"
+                  type="text"
+                  value={xtream.server}
+                  onChange={(e) => setXtream({ ...xtream, server: e.target.value })}
+                  placeholder="http://example.com:8080"
+                  required
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="xtream-username">用户名</label>
+                <input
+                  id="xtream-username"
+                  type="text"
+                  value={xtream.username}
+                  onChange={(e) => setXtream({ ...xtream, username: e.target.value })}
+                  required
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="xtream-password">密码</label>
+                <input
+                  id="xtream-password"
+                  type="password"
+                  value={xtream.password}
+                  onChange={(e) => setXtream({ ...xtream, password: e.target.value })}
+                  required
+                />
+              </div>
+            </>
+          )}
+
+          {/* HTTP 设置：网络地址和 Xtream */}
+          {((sourceType === "url" && !useDemo) || sourceType === "xtream") && (
+            <details className="form-group">
+              <summary style={{ cursor: "pointer", marginBottom: "8px" }}>HTTP 设置（User-Agent、请求头、认证）</summary>
+              <div className="form-group">
+                <label htmlFor="http-user-agent">User-Agent</label>
+                <select
+                  id="http-user-agent"
+                  value={userAgent}
+                  onChange={(e) => setUserAgent(e.target.value as UserAgentPreset["type"])}
+                >
+                  <option value="default">默认</option>
+                  <option value="chrome">Chrome</option>
+                  <option value="vlc">VLC</option>
+                  <option value="kodi">Kodi</option>
+                  <option value="tivimate">TiviMate</option>
+                  <option value="okhttp">okhttp（TVBox）</option>
+                  <option value="custom">自定义</option>
+                </select>
+                {userAgent === "custom" && (
+                  <input
+                    type="text"
+                    value={customUserAgent}
+                    onChange={(e) => setCustomUserAgent(e.target.value)}
+                    placeholder="自定义 User-Agent"
+                    style={{ marginTop: "8px" }}
+                  />
+                )}
+              </div>
+              <div className="form-group">
+                <label htmlFor="http-headers">额外请求头</label>
+                <textarea
+                  id="http-headers"
+                  value={headersText}
+                  onChange={(e) => setHeadersText(e.target.value)}
+                  placeholder={"Referer: https://example.com/\nCookie: token=..."}
+                  rows={3}
+                  style={{ width: "100%", fontFamily: "monospace", fontSize: "12px", resize: "vertical" }}
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="http-auth">认证</label>
+                <select
+                  id="http-auth"
+                  value={authType}
+                  onChange={(e) => setAuthType(e.target.value as "none" | HttpAuth["type"])}
+                >
+                  <option value="none">无</option>
+                  <option value="basic">Basic（用户名 / 密码）</option>
+                  <option value="bearer">Bearer Token</option>
+                </select>
+                {authType === "basic" && (
+                  <div style={{ display: "flex", gap: "8px", marginTop: "8px" }}>
+                    <input
+                      type="text"
+                      value={authUsername}
+                      onChange={(e) => setAuthUsername(e.target.value)}
+                      placeholder="用户名"
+                    />
+                    <input
+                      type="password"
+                      value={authPassword}
+                      onChange={(e) => setAuthPassword(e.target.value)}
+                      placeholder="密码"
+                    />
+                  </div>
+                )}
+                {authType === "bearer" && (
+                  <input
+                    type="password"
+                    value={authToken}
+                    onChange={(e) => setAuthToken(e.target.value)}
+                    placeholder="Token"
+                    style={{ marginTop: "8px" }}
+                  />
+                )}
+              </div>
+              <div className="form-group">
+                <label htmlFor="http-timeout">超时（秒）</label>
+                <input
+                  id="http-timeout"
+                  type="number"
+                  min={1}
+                  value={timeoutSecs}
+                  onChange={(e) => setTimeoutSecs(e.target.value)}
+                  placeholder="30"
+                />
+              </div>
+              <label style={{ display: "flex", alignItems: "center", gap: "8px", cursor: "pointer" }}>
+                <input
+                  type="checkbox"
+                  checked={acceptInvalidCerts}
+                  onChange={(e) => setAcceptInvalidCerts(e.target.checked)}
+                />
+                接受无效的 HTTPS 证书
+              </label>
+              <label style={{ display: "flex", alignItems: "center", gap: "8px", cursor: "pointer", marginTop: "8px" }}>
+                <input
+                  type="checkbox"
+                  checked={applyToStreams}
+                  onChange={(e) => setApplyToStreams(e.target.checked)}
+                />
+                播放频道时也使用这些设置
+              </label>
+            </details>
+          )}
+
+          <div className="form-group">
+            <label style={{ display: "flex", alignItems: "center", gap: "8px", cursor: "pointer" }}>
+              <input
+                type="checkbox"
+                checked={mergeDuplicates}
+                onChange={(e) => setMergeDuplicates(e.target.checked)}
+              />
+              合并重复频道（其余地址作为备用线路）
+            </label>
+          </div>
+
+          <div className="form-group">
+            <label htmlFor="refresh-interval">自动刷新</label>
+            <select
+              id="refresh-interval"
+              value={refreshInterval}
+              onChange={(e) => setRefreshInterval(Number(e.target.value))}
+            >
+              <option value={0}>不自动刷新</option>
+              <option value={30}>每 30 分钟</option>
+              <option value={60}>每 1 小时</option>
+              <option value={360}>每 6 小时</option>
+              <option value={720}>每 12 小时</option>
+              <option value={1440}>每天</option>
+            </select>
+          </div>
+
           <div className="modal-actions">
             <button type="button" onClick={onClose} className="secondary-btn">
               取消
@@ -1,13 +1,15 @@
 import { useState, useMemo } from "react";
-import type { Channel } from "../App";
+import type { Channel, NowNext } from "../App";
 
 interface ChannelListProps {
   channels: Channel[];
   selectedChannel: Channel | null;
+  nowNext?: Record<string, NowNext>; // 频道地址 -> 当前 / 下一个节目
+
   onSelectChannel: (channel: Channel) => void;
 }
 
-function ChannelList({ channels, selectedChannel, onSelectChannel }: ChannelListProps) {
+function ChannelList({ channels, selectedChannel, nowNext, onSelectChannel }: ChannelListProps) {
   const [searchTerm, setSearchTerm] = useState("");
 
   // 按分组整理频道
@@ -105,6 +107,14 @@ function ChannelList({ channels, selectedChannel, onSelectChannel }: ChannelList
                         <span style={{ fontSize: '10px', marginLeft: '5px', opacity: 0.6 }}>IPv6</span>
                       )}
                     </div>
+                    {nowNext?.[channel.url]?.now && (
+                      <div
+                        className="channel-now"
+                        title={nowNext[channel.url].next ? `下一个: ${nowNext[channel.url].next?.title}` : undefined}
+                      >
+                        ▶ {nowNext[channel.url].now?.title}
+                      </div>
+                    )}
                   </div>
                 </div>
               ))}
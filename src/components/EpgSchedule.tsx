@@ -0,0 +1,99 @@
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { Channel, Programme } from "../App";
+
+interface EpgScheduleProps {
+  sourceId: string;
+  channel: Channel;
+}
+
+// 本地日期 YYYY-MM-DD
+function formatDate(date: Date): string {
+  const pad = (n: number) => String(n).padStart(2, "0");
+  return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+}
+
+function formatTime(timestamp: number): string {
+  return new Date(timestamp * 1000).toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" });
+}
+
+// 频道某一天的节目单
+function EpgSchedule({ sourceId, channel }: EpgScheduleProps) {
+  const [date, setDate] = useState(() => formatDate(new Date()));
+  const [programmes, setProgrammes] = useState<Programme[]>([]);
+  const [now, setNow] = useState(() => Date.now() / 1000);
+  const currentRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    const load = async () => {
+      try {
+        // 按当天的时区偏移计算（夏令时切换的日期偏移不同）
+        const utcOffsetMinutes = -new Date(`${date}T00:00:00`).getTimezoneOffset();
+        const result = await invoke<Programme[]>("get_epg_day", { sourceId, url: channel.url, date, utcOffsetMinutes });
+        if (!cancelled) setProgrammes(result);
+      } catch (error) {
+        console.error("❌ 加载节目单失败:", error);
+        if (!cancelled) setProgrammes([]);
+      }
+    };
+    load();
+
+    // 节目单下载完成后重新加载
+    const unlisten = listen("epg-updated", load);
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+    };
+  }, [sourceId, channel, date]);
+
+  // 每分钟更新正在播放的节目
+  useEffect(() => {
+    const timer = setInterval(() => setNow(Date.now() / 1000), 60_000);
+    return () => clearInterval(timer);
+  }, []);
+
+  useEffect(() => {
+    currentRef.current?.scrollIntoView({ block: "center" });
+  }, [programmes]);
+
+  const shiftDate = (days: number) => {
+    const next = new Date(`${date}T00:00:00`);
+    next.setDate(next.getDate() + days);
+    setDate(formatDate(next));
+  };
+
+  return (
+    <div className="epg-schedule">
+      <div className="epg-schedule-header">
+        <button type="button" onClick={() => shiftDate(-1)} title="前一天">‹</button>
+        <span>📅 {date}</span>
+        <button type="button" onClick={() => shiftDate(1)} title="后一天">›</button>
+      </div>
+      {programmes.length === 0 ? (
+        <div className="epg-empty">暂无节目信息</div>
+      ) : (
+        programmes.map((programme) => {
+          const playing = programme.start <= now && now < programme.stop;
+          return (
+            <div
+              key={programme.start}
+              ref={playing ? currentRef : undefined}
+              className={`epg-programme ${playing ? "playing" : ""} ${programme.stop <= now ? "past" : ""}`}
+              title={programme.desc}
+            >
+              <span className="epg-time">{formatTime(programme.start)}</span>
+              <span className="epg-title">
+                {programme.title}
+                {programme.episode ? ` ${programme.episode}` : ""}
+              </span>
+            </div>
+          );
+        })
+      )}
+    </div>
+  );
+}
+
+export default EpgSchedule;
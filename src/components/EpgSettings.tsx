@@ -0,0 +1,179 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { ChannelAlias, EpgFeedStatus, EpgGlobalSettings } from "../App";
+
+interface EpgSettingsProps {
+  onClose: () => void;
+}
+
+// 每行一个 "频道名 = 节目单中的名称"
+function formatAliases(aliases: ChannelAlias[]): string {
+  return aliases.map((alias) => `${alias.name} = ${alias.target}`).join("\n");
+}
+
+function parseAliases(text: string): ChannelAlias[] {
+  return text
+    .split("\n")
+    .map((line) => line.split("="))
+    .filter((parts) => parts.length === 2 && parts[0].trim() && parts[1].trim())
+    .map(([name, target]) => ({ name: name.trim(), target: target.trim() }));
+}
+
+function formatFetchedAt(timestamp?: number): string {
+  return timestamp ? new Date(timestamp * 1000).toLocaleString() : "未下载";
+}
+
+// 节目单地址、优先级和频道匹配设置
+function EpgSettings({ onClose }: EpgSettingsProps) {
+  const [globalUrlsText, setGlobalUrlsText] = useState(""); // 每行一个地址，靠前的优先
+  const [preferGlobal, setPreferGlobal] = useState(false);
+  const [aliasesText, setAliasesText] = useState("");
+  const [feeds, setFeeds] = useState<EpgFeedStatus[]>([]);
+  const [refreshing, setRefreshing] = useState(false);
+  const [saving, setSaving] = useState(false);
+
+  const loadFeeds = async () => {
+    try {
+      setFeeds(await invoke<EpgFeedStatus[]>("get_epg_feeds"));
+    } catch (error) {
+      console.error("❌ 加载节目单状态失败:", error);
+    }
+  };
+
+  useEffect(() => {
+    invoke<EpgGlobalSettings>("get_epg_settings")
+      .then((settings) => {
+        setGlobalUrlsText(settings.global_urls.join("\n"));
+        setPreferGlobal(settings.prefer_global);
+      })
+      .catch((error) => console.error("❌ 加载节目单设置失败:", error));
+    invoke<ChannelAlias[]>("get_channel_aliases")
+      .then((aliases) => setAliasesText(formatAliases(aliases)))
+      .catch((error) => console.error("❌ 加载频道别名失败:", error));
+    loadFeeds();
+
+    // 节目单下载完成后更新状态
+    const unlisten = listen("epg-updated", loadFeeds);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleRefreshAll = async () => {
+    setRefreshing(true);
+    try {
+      await invoke<EpgFeedStatus[]>("refresh_epg", { sourceId: null });
+      await loadFeeds();
+    } catch (error) {
+      console.error("❌ 刷新节目单失败:", error);
+      alert(`刷新节目单失败: ${error}`);
+    } finally {
+      setRefreshing(false);
+    }
+  };
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setSaving(true);
+    try {
+      const globalUrls = globalUrlsText.split("\n").map((url) => url.trim()).filter(Boolean);
+      await invoke<EpgGlobalSettings>("set_epg_settings", { settings: { global_urls: globalUrls, prefer_global: preferGlobal } });
+      await invoke<ChannelAlias[]>("set_channel_aliases", { aliases: parseAliases(aliasesText) });
+      onClose();
+    } catch (error) {
+      console.error("❌ 保存节目单设置失败:", error);
+      alert(`保存节目单设置失败: ${error}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="modal-overlay" onClick={onClose}>
+      <div className="modal" onClick={(e) => e.stopPropagation()}>
+        <h2>节目单设置</h2>
+        <form onSubmit={handleSubmit}>
+          <div className="form-group">
+            <label htmlFor="epg-global-urls">全局节目单</label>
+            <textarea
+              id="epg-global-urls"
+              value={globalUrlsText}
+              onChange={(e) => setGlobalUrlsText(e.target.value)}
+              placeholder={"https://epg.example.com/e.xml.gz\nhttps://epg.example.com/api/?ch={name}&date={date}"}
+              rows={3}
+              style={{ width: "100%", fontFamily: "monospace", fontSize: "12px", resize: "vertical" }}
+            />
+            <p style={{ fontSize: "12px", color: "#999", marginTop: "6px" }}>
+              所有订阅源都会使用，每行一个 XMLTV 或 DIYP 接口地址，靠前的优先。同一频道在多个节目单中都有节目时，使用优先级最高、且在所查时间段内有节目的节目单。
+            </p>
+            <label style={{ display: "flex", alignItems: "center", gap: "8px", cursor: "pointer" }}>
+              <input
+                type="checkbox"
+                checked={preferGlobal}
+                onChange={(e) => setPreferGlobal(e.target.checked)}
+              />
+              全局节目单优先于订阅源自带的节目单（x-tvg-url）
+            </label>
+          </div>
+
+          <div className="form-group">
+            <label htmlFor="channel-aliases">频道别名</label>
+            <textarea
+              id="channel-aliases"
+              value={aliasesText}
+              onChange={(e) => setAliasesText(e.target.value)}
+              placeholder={"凤凰中文 = 凤凰卫视中文台\n东方卫视 = 上海东方卫视"}
+              rows={5}
+              style={{ width: "100%", fontFamily: "monospace", fontSize: "12px", resize: "vertical" }}
+            />
+            <p style={{ fontSize: "12px", color: "#999", marginTop: "6px" }}>
+              频道名会自动忽略分隔符、全角字符、繁简差异和 HD / 高清 等后缀（CCTV-1综合、CCTV 1 HD 都按 CCTV1 匹配），
+              仍然匹配不到节目单或台标时，在这里每行填写一个 "频道名 = 节目单中的名称"。
+            </p>
+          </div>
+
+          <div className="form-group">
+            <div style={{ display: "flex", alignItems: "center", justifyContent: "space-between" }}>
+              <label>节目单状态</label>
+              <button type="button" onClick={handleRefreshAll} className="secondary-btn" disabled={refreshing || feeds.length === 0}>
+                {refreshing ? "下载中..." : "全部刷新"}
+              </button>
+            </div>
+            {feeds.length === 0 ? (
+              <p className="epg-empty">没有节目单地址</p>
+            ) : (
+              <div className="epg-feeds">
+                {feeds.map((feed) => (
+                  <div key={feed.id} className="epg-feed">
+                    <div className="epg-feed-url" title={feed.url}>
+                      <span className="epg-feed-kind">{feed.kind === "diyp" ? "DIYP" : "XMLTV"}</span>
+                      {feed.url}
+                    </div>
+                    <div className="epg-feed-info">
+                      {feed.kind === "diyp" ? "最近查询" : "最近下载"}: {formatFetchedAt(feed.fetched_at)}
+                      {" · "}
+                      {feed.channel_count} 个频道，{feed.programme_count.toLocaleString()} 个节目
+                    </div>
+                    {feed.error && <div className="epg-feed-error">❌ {feed.error}</div>}
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
+
+          <div className="modal-actions">
+            <button type="button" onClick={onClose} className="secondary-btn">
+              取消
+            </button>
+            <button type="submit" className="primary-btn" disabled={saving}>
+              {saving ? "保存中..." : "保存"}
+            </button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
+
+export default EpgSettings;
@@ -1,3 +1,4 @@
+import { describeSourceKind } from "../App";
 import type { Source } from "../App";
 
 interface SourceListProps {
@@ -6,7 +7,17 @@ interface SourceListProps {
   onSelectSource: (source: Source) => void;
   onDeleteSource: (sourceId: string) => Promise<void>;
   onEditSource: (source: Source) => void;
+  onRefreshSource: (source: Source) => void;
+  onRefreshAll: () => void;
+  onRefreshEpg: (source: Source) => void;
+  onExportSource: (source: Source) => void;
   onAddSource: () => void;
+  onOpenEpgSettings: () => void;
+}
+
+// 刷新间隔（分钟）显示为 "30 分钟" / "6 小时"
+function formatInterval(minutes: number): string {
+  return minutes % 60 === 0 ? `${minutes / 60} 小时` : `${minutes} 分钟`;
 }
 
 function SourceList({
@@ -15,15 +26,30 @@ function SourceList({
   onSelectSource,
   onDeleteSource,
   onEditSource,
+  onRefreshSource,
+  onRefreshAll,
+  onRefreshEpg,
+  onExportSource,
   onAddSource,
+  onOpenEpgSettings,
 }: SourceListProps) {
   return (
     <>
       <div className="source-list-header">
         <h3>订阅源</h3>
-        <button onClick={onAddSource} className="add-source-btn" title="添加订阅源">
-          ➕
-        </button>
+        <div style={{ display: "flex", gap: "6px" }}>
+          {sources.length > 0 && (
+            <button onClick={onRefreshAll} className="add-source-btn" title="刷新全部订阅源">
+              🔄
+            </button>
+          )}
+          <button onClick={onOpenEpgSettings} className="add-source-btn" title="节目单设置">
+            📅
+          </button>
+          <button onClick={onAddSource} className="add-source-btn" title="添加订阅源">
+            ➕
+          </button>
+        </div>
       </div>
 
       <div className="source-list-content">
@@ -42,8 +68,23 @@ function SourceList({
               onClick={() => onSelectSource(source)}
             >
               <div className="source-info">
-                <div className="source-name">📺 {source.name}</div>
-                <div className="source-count">{source.channels.length} 个频道</div>
+                <div className="source-name" title={describeSourceKind(source.kind)}>📺 {source.name}</div>
+                <div className="source-count">
+                  {source.channels.length} 个频道
+                  {source.encoding && source.encoding !== "UTF-8" ? ` · ${source.encoding}` : ""}
+                  {source.refresh_interval ? ` · 每 ${formatInterval(source.refresh_interval)}刷新` : ""}
+                  {source.watch_file ? " · 监视文件" : ""}
+                </div>
+                {source.last_refreshed && (
+                  <div className="source-count" style={{ opacity: 0.6 }}>
+                    更新于 {new Date(source.last_refreshed * 1000).toLocaleString()}
+                  </div>
+                )}
+                {source.last_error && (
+                  <div className="source-count" style={{ color: "#ff6b6b" }} title={source.last_error}>
+                    ⚠️ {source.file_missing ? "文件不存在" : "刷新失败"}，显示的是上次的频道
+                  </div>
+                )}
               </div>
               <div className="source-actions">
                 <button
@@ -60,6 +101,51 @@ function SourceList({
                     <path d="M18.5 2.5a2.121 2.121 0 0 1 3 3L12 15l-4 1 1-4 9.5-9.5z"></path>
                   </svg>
                 </button>
+                <button
+                  onClick={(e) => {
+                    e.stopPropagation();
+                    onRefreshSource(source);
+                  }}
+                  className="action-btn edit-btn"
+                  title="刷新订阅源"
+                >
+                  <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+                    <polyline points="23 4 23 10 17 10"></polyline>
+                    <polyline points="1 20 1 14 7 14"></polyline>
+                    <path d="M3.51 9a9 9 0 0 1 14.85-3.36L23 10M1 14l4.64 4.36A9 9 0 0 0 20.49 15"></path>
+                  </svg>
+                </button>
+                {source.meta?.epg_urls && source.meta.epg_urls.length > 0 && (
+                  <button
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      onRefreshEpg(source);
+                    }}
+                    className="action-btn edit-btn"
+                    title="刷新节目单"
+                  >
+                    <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+                      <rect x="3" y="4" width="18" height="18" rx="2" ry="2"></rect>
+                      <line x1="16" y1="2" x2="16" y2="6"></line>
+                      <line x1="8" y1="2" x2="8" y2="6"></line>
+                      <line x1="3" y1="10" x2="21" y2="10"></line>
+                    </svg>
+                  </button>
+                )}
+                <button
+                  onClick={(e) => {
+                    e.stopPropagation();
+                    onExportSource(source);
+                  }}
+                  className="action-btn edit-btn"
+                  title="导出订阅源"
+                >
+                  <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+                    <path d="M21 15v4a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2v-4"></path>
+                    <polyline points="7 10 12 15 17 10"></polyline>
+                    <line x1="12" y1="15" x2="12" y2="3"></line>
+                  </svg>
+                </button>
                 <button
                   onClick={async (e) => {
                     e.stopPropagation();
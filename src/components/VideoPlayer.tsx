@@ -2,13 +2,25 @@ import { useEffect, useRef, useState } from "react";
 import Hls from "hls.js";
 // @ts-ignore - 保留用于未来功能
 import { invoke } from "@tauri-apps/api/core";
-import type { Channel } from "../App";
+import type { Channel, HlsVariant } from "../App";
 
 interface VideoPlayerProps {
   channel: Channel | null;
+  sourceId?: string | null; // 频道所属的订阅源，用于附带订阅源的 HTTP 设置
 }
 
-function VideoPlayer({ channel }: VideoPlayerProps) {
+function formatVariant(variant: HlsVariant): string {
+  const parts: string[] = [];
+  if (variant.resolution) parts.push(variant.resolution);
+  if (variant.bandwidth) {
+    parts.push(variant.bandwidth >= 1_000_000
+      ? `${(variant.bandwidth / 1_000_000).toFixed(1)} Mbps`
+      : `${Math.round(variant.bandwidth / 1000)} kbps`);
+  }
+  return parts.join(" · ") || variant.url;
+}
+
+function VideoPlayer({ channel, sourceId }: VideoPlayerProps) {
   const videoRef = useRef<HTMLVideoElement>(null);
   const hlsRef = useRef<Hls | null>(null);
   const [error, setError] = useState<string | null>(null);
@@ -17,14 +29,51 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
   const blobUrlRef = useRef<string | null>(null);
   const retryCountRef = useRef<number>(0);
   const maxRetries = 3;
+  // 当前使用的线路（0 为主地址，之后为 backup_urls）
+  const [urlIndex, setUrlIndex] = useState(0);
+  const lastChannelRef = useRef<Channel | null>(null);
+  // 手动选择的清晰度（-1 为自动）
+  const [variantIndex, setVariantIndex] = useState(-1);
 
   useEffect(() => {
     if (!videoRef.current || !channel) return;
 
+    // 切换频道时从主地址开始
+    if (lastChannelRef.current !== channel) {
+      lastChannelRef.current = channel;
+      setVariantIndex(-1);
+      if (urlIndex !== 0) {
+        setUrlIndex(0);
+        return;
+      }
+    }
+
+    const candidateUrls = [channel.url, ...(channel.backup_urls ?? [])];
+    const currentUrl = candidateUrls[urlIndex] ?? channel.url;
+
     setError(null);
     setLoading(true);
 
     const video = videoRef.current;
+    video.onerror = null;
+
+    // 当前线路无法播放时切换到下一个备用地址，返回是否还有备用地址
+    const switchToNextUrl = () => {
+      if (urlIndex + 1 >= candidateUrls.length) return false;
+      console.log(`🔀 切换到备用线路 ${urlIndex + 2}/${candidateUrls.length}`);
+      setBufferInfo(`🔀 切换到备用线路 ${urlIndex + 2}/${candidateUrls.length}...`);
+      setUrlIndex(urlIndex + 1);
+      return true;
+    };
+
+    // 非 HLS.js 播放（原生 HLS 和普通视频流）出错时的处理
+    const handleVideoError = () => {
+      console.error("❌ 视频加载失败:", video.error?.message ?? video.error?.code);
+      if (!switchToNextUrl()) {
+        setError("播放失败: 视频加载失败");
+        setLoading(false);
+      }
+    };
 
     // 清理之前的实例
     if (hlsRef.current) {
@@ -38,13 +87,13 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
       blobUrlRef.current = null;
     }
 
-    console.log("原始 URL:", channel.url);
+    console.log(`原始 URL (线路 ${urlIndex + 1}/${candidateUrls.length}):`, currentUrl);
 
     // 重置重试计数器
     retryCountRef.current = 0;
 
     // 检查是否是 IPv6 URL
-    const isIpv6 = channel.url.includes('[') && channel.url.includes(']');
+    const isIpv6 = currentUrl.includes('[') && currentUrl.includes(']');
 
     // 🚀 预连接优化 - 提前建立 TCP 连接到代理服务器
     const preconnectToProxy = () => {
@@ -65,16 +114,25 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
 
     // 异步加载视频
     const loadVideo = async () => {
-      let processedUrl = channel.url;
+      let processedUrl = currentUrl;
 
       // ⭐ 所有 m3u8 都走代理（修复混合内容问题 + IPv6支持）
-      if (channel.url.includes(".m3u8")) {
+      if (currentUrl.includes(".m3u8")) {
         console.log("🌐 检测到 m3u8，通过代理访问");
 
         // 直接将原始 URL 编码后传给代理服务器
-        const encodedUrl = encodeURIComponent(channel.url);
+        const encodedUrl = encodeURIComponent(currentUrl);
         processedUrl = `http://127.0.0.1:18080/proxy?url=${encodedUrl}`;
 
+        // 频道自带的请求头（#EXTVLCOPT / #KODIPROP）和订阅源的 HTTP 设置交给代理使用
+        if (channel.http || sourceId) {
+          const hid = await invoke<string | null>("register_stream_headers", { http: channel.http ?? null, sourceId: sourceId ?? null });
+          if (hid) {
+            processedUrl += `&hid=${hid}`;
+            console.log("🔑 使用自定义请求头:", channel.http ?? sourceId);
+          }
+        }
+
         console.log("🔄 代理 URL:", processedUrl);
         if (isIpv6) {
           console.log("  (IPv6 URL)");
@@ -315,7 +373,8 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
                   setBufferInfo('');
                 }
               }, delay);
-            } else {
+            } else if (!switchToNextUrl()) {
+              // ⭐ 当前线路失败且没有备用地址
               setError(`播放失败: ${errorDescription}`);
               setLoading(false);
             }
@@ -388,6 +447,7 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
         // x-iptv-player 没有任何播放监控逻辑，只依赖 HLS.js 的内置机制
         } else if (video.canPlayType("application/vnd.apple.mpegurl")) {
           // Safari 原生支持 HLS
+          video.onerror = handleVideoError;
           video.src = processedUrl;
           video.addEventListener("loadedmetadata", () => {
             video.play().catch((err) => {
@@ -402,6 +462,7 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
         }
     } else {
       // 普通视频流
+        video.onerror = handleVideoError;
         video.src = processedUrl;
         video.addEventListener("loadedmetadata", () => {
           video.play().catch((err) => {
@@ -417,6 +478,7 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
     loadVideo();
 
     return () => {
+      video.onerror = null;
       if (hlsRef.current) {
         hlsRef.current.destroy();
       }
@@ -424,7 +486,25 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
         URL.revokeObjectURL(blobUrlRef.current);
       }
     };
-  }, [channel]);
+  }, [channel, sourceId, urlIndex]);
+
+  // 选择清晰度：按码率或分辨率匹配 HLS.js 的 level，找不到时回到自动
+  const handleVariantChange = (index: number) => {
+    setVariantIndex(index);
+    const hls = hlsRef.current;
+    const variant = channel?.variants?.[index];
+    if (!hls) return;
+    if (!variant) {
+      hls.currentLevel = -1;
+      return;
+    }
+    const level = hls.levels.findIndex(l =>
+      (variant.bandwidth !== undefined && l.bitrate === variant.bandwidth) ||
+      (variant.resolution !== undefined && `${l.width}x${l.height}` === variant.resolution)
+    );
+    console.log(`手动切换清晰度: ${formatVariant(variant)} -> level ${level}`);
+    hls.currentLevel = level;
+  };
 
   const handleManualPlay = () => {
     if (videoRef.current) {
@@ -482,6 +562,32 @@ function VideoPlayer({ channel }: VideoPlayerProps) {
           {bufferInfo}
         </div>
       )}
+      {!error && channel?.variants && channel.variants.length > 1 && (
+        <select
+          value={variantIndex}
+          onChange={(e) => handleVariantChange(Number(e.target.value))}
+          title="清晰度"
+          style={{
+            position: "absolute",
+            top: "10px",
+            left: "10px",
+            backgroundColor: "rgba(0, 0, 0, 0.7)",
+            color: "#fff",
+            border: "none",
+            borderRadius: "4px",
+            padding: "4px 8px",
+            fontSize: "12px",
+            zIndex: 100
+          }}
+        >
+          <option value={-1}>自动</option>
+          {channel.variants.map((variant, index) => (
+            <option key={variant.url} value={index} title={variant.codecs}>
+              {formatVariant(variant)}
+            </option>
+          ))}
+        </select>
+      )}
       {!channel && (
         <div className="video-error">
           <p>请选择一个频道开始播放</p>